@@ -0,0 +1,123 @@
+use crate::image_cache::{extension, write_whole};
+use crate::logs::safe_name;
+use crate::{Game, Launcher};
+use anyhow::{anyhow, Error, Result};
+use reqwest;
+use reqwest::header::CONTENT_TYPE;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Extensions looked for in the artwork folder, in order of preference.
+const EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "gif", "webp", "bmp", "ico"];
+
+/// Covers dropped into `artwork/<launcher>/<id>.png` (or named after the game's
+/// title, in the launcher's folder or the artwork folder itself) replace
+/// whatever image the game's source provides. Titles may be written as they are,
+/// `Hollow Knight.png`, or with anything but letters, digits and dashes replaced
+/// by underscores, `Hollow_Knight.png`, as `art set` names them.
+pub struct Artwork {
+    folder: PathBuf,
+}
+
+impl Artwork {
+    pub fn new(folder: &PathBuf) -> Artwork {
+        Artwork {
+            folder: folder.clone(),
+        }
+    }
+
+    fn launcher_folder(&self, launcher: Launcher) -> PathBuf {
+        self.folder.join(format!("{:?}", launcher).to_lowercase())
+    }
+
+    /// Names an override may have without its extension, best match first.
+    fn stems(&self, game: &Game) -> Vec<PathBuf> {
+        let folder = self.launcher_folder(game.launcher);
+        let mut titles = vec![safe_name(&game.title)];
+        // Titles that can't be file names are only found by their safe name.
+        let unsafe_chars = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+        if titles[0] != game.title && !game.title.contains(&unsafe_chars[..]) {
+            titles.push(game.title.clone());
+        }
+        let mut stems = vec![folder.join(safe_name(&game.id))];
+        stems.extend(titles.iter().map(|title| folder.join(title)));
+        stems.extend(titles.iter().map(|title| self.folder.join(title)));
+        stems
+    }
+
+    /// Every override, in the artwork folder or a launcher's folder inside it.
+    pub fn files(&self) -> Vec<PathBuf> {
+        let list = |folder: &PathBuf| -> Vec<PathBuf> {
+            match fs::read_dir(folder) {
+                Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+                Err(_) => Vec::new(),
+            }
+        };
+        list(&self.folder)
+            .into_iter()
+            .flat_map(|path| {
+                if path.is_dir() {
+                    list(&path)
+                } else {
+                    vec![path]
+                }
+            })
+            .filter(|path| path.is_file())
+            .collect()
+    }
+
+    /// The override for a game, if one has been put in place.
+    pub fn find(&self, game: &Game) -> Option<PathBuf> {
+        self.stems(game).into_iter().find_map(|stem| {
+            EXTENSIONS
+                .iter()
+                .map(|ext| {
+                    // Not `with_extension`, which would cut titles at a dot.
+                    let mut path = stem.clone().into_os_string();
+                    path.push(".");
+                    path.push(ext);
+                    PathBuf::from(path)
+                })
+                .find(|path| path.is_file())
+        })
+    }
+
+    /// Copies `source`, a file or URL, into place as the game's override and
+    /// returns where it went. Fails without touching anything unless it decodes.
+    pub fn set(&self, game: &Game, source: &str) -> Result<PathBuf, Error> {
+        let (contents, content_type) =
+            if source.starts_with("http://") || source.starts_with("https://") {
+                let mut resp = reqwest::get(source)?;
+                if !resp.status().is_success() {
+                    return Err(anyhow!("{} returned {}", source, resp.status()));
+                }
+                let content_type = resp
+                    .headers()
+                    .get(CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.to_owned());
+                let mut contents = Vec::new();
+                resp.read_to_end(&mut contents)?;
+                (contents, content_type)
+            } else {
+                (fs::read(source)?, None)
+            };
+        image::load_from_memory(&contents)
+            .map_err(|err| anyhow!("{} isn't a usable image: {}", source, err))?;
+
+        let stem = self.stems(game).remove(0);
+        // An override with another extension would otherwise still be found first.
+        for ext in EXTENSIONS.iter() {
+            let old = stem.with_extension(ext);
+            if old.is_file() {
+                fs::remove_file(&old)?;
+            }
+        }
+        let ext = extension(content_type.as_deref(), &contents, source);
+        let path = stem.with_extension(ext);
+        fs::create_dir_all(path.parent().unwrap())?;
+        write_whole(&path, &contents)?;
+        Ok(path)
+    }
+}
@@ -0,0 +1,115 @@
+use crate::crop::CropMode;
+use crate::{ArtKind, Game};
+use anyhow::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json;
+use std::fs;
+use std::path::PathBuf;
+
+const CONFIG_FILE: &str = "config.json";
+
+/// How many games may be running at once. Unset limits don't apply. Games
+/// launched through doorways count, as do Steam games started from Steam; those
+/// started from other stores' clients can't be seen.
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct Limits {
+    pub max_running: Option<usize>,
+    /// Applies to games marked for kids.
+    pub kids_max_running: Option<usize>,
+    /// Applies to games marked as not for kids.
+    pub dad_max_running: Option<usize>,
+}
+
+impl Limits {
+    /// Explains why `game` can't be launched while `running` are, if a limit is reached.
+    pub fn check(&self, game: &Game, running: &[&Game]) -> Option<String> {
+        if let Some(max) = self.max_running {
+            if running.len() >= max {
+                return Some(format!(
+                    "{} of {} games already running. Close one first.",
+                    running.len(),
+                    max
+                ));
+            }
+        }
+        let (max, profile) = match game.kids {
+            Some(true) => (self.kids_max_running, "Kids"),
+            Some(false) => (self.dad_max_running, "Dad"),
+            None => return None,
+        };
+        let max = max?;
+        let count = running.iter().filter(|g| g.kids == game.kids).count();
+        if count >= max {
+            return Some(format!(
+                "{} can only play {} game{} at a time. Close one first.",
+                profile,
+                max,
+                if max == 1 { "" } else { "s" }
+            ));
+        }
+        None
+    }
+}
+
+/// Settings edited by hand in `config.json` next to `games.json`.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Config {
+    #[serde(default)]
+    pub limits: Limits,
+    /// Back saves up whenever a session ends, for games listed in `saves.json`.
+    #[serde(default)]
+    pub backup_saves: bool,
+    /// Kinds of artwork tiles use, most preferred first. Games with none of them
+    /// fall back to whatever image their source gave.
+    #[serde(default = "default_artwork")]
+    pub artwork: Vec<ArtKind>,
+    /// Crop every tile to `tile_aspect` instead of fitting images inside their
+    /// tile. Ctrl+T switches while the launcher is open.
+    #[serde(default)]
+    pub uniform_tiles: bool,
+    /// Width divided by height of uniform tiles.
+    #[serde(default = "default_tile_aspect")]
+    pub tile_aspect: f32,
+    #[serde(default)]
+    pub crop: CropMode,
+    /// Play animated covers on the selected tile.
+    #[serde(default = "default_animate_covers")]
+    pub animate_covers: bool,
+}
+
+fn default_animate_covers() -> bool {
+    true
+}
+
+fn default_tile_aspect() -> f32 {
+    // Box art.
+    2.0 / 3.0
+}
+
+fn default_artwork() -> Vec<ArtKind> {
+    vec![ArtKind::Cover, ArtKind::Banner]
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            limits: Limits::default(),
+            backup_saves: false,
+            artwork: default_artwork(),
+            uniform_tiles: false,
+            tile_aspect: default_tile_aspect(),
+            crop: CropMode::default(),
+            animate_covers: default_animate_covers(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load(cache_dir: &PathBuf) -> Result<Config, Error> {
+        let path = cache_dir.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+}
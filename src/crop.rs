@@ -0,0 +1,107 @@
+use image::{GenericImageView, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+/// Which part of an image is kept when cropping it to the tile shape.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum CropMode {
+    #[default]
+    Center,
+    /// The part with the most detail, so titles and faces aren't cut off as often.
+    Saliency,
+}
+
+/// A shape every tile is cropped to.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Crop {
+    /// Width divided by height.
+    pub aspect: f32,
+    pub mode: CropMode,
+}
+
+impl Crop {
+    /// Distinguishes thumbnails cropped differently.
+    pub fn key(&self) -> String {
+        let mode = match self.mode {
+            CropMode::Center => "center",
+            CropMode::Saliency => "saliency",
+        };
+        format!("{}{:.3}", mode, self.aspect)
+    }
+
+    /// The largest size with this shape that fits in `width` by `height`.
+    pub fn fit(&self, width: usize, height: usize) -> (usize, usize) {
+        if width as f32 / height as f32 > self.aspect {
+            ((height as f32 * self.aspect) as usize, height)
+        } else {
+            (width, (width as f32 / self.aspect) as usize)
+        }
+    }
+
+    /// Cuts `img` down to this shape along its longer side.
+    pub fn apply(&self, img: &RgbaImage) -> RgbaImage {
+        let (width, height) = (img.width(), img.height());
+        let wide = width as f32 / height as f32 > self.aspect;
+        let (crop_width, crop_height) = if wide {
+            (((height as f32 * self.aspect) as u32).max(1), height)
+        } else {
+            (width, ((width as f32 / self.aspect) as u32).max(1))
+        };
+        let along = if wide { width } else { height };
+        let window = if wide { crop_width } else { crop_height };
+        let offset = match self.mode {
+            CropMode::Center => (along - window) / 2,
+            CropMode::Saliency => busiest_window(&energy(img, wide), window as usize) as u32,
+        };
+        let (x, y) = if wide { (offset, 0) } else { (0, offset) };
+        img.view(x, y, crop_width, crop_height).to_image()
+    }
+}
+
+/// Brightness of every pixel, row by row.
+fn luma(img: &RgbaImage) -> Vec<i32> {
+    img.pixels()
+        .map(|p| (p[0] as i32 * 299 + p[1] as i32 * 587 + p[2] as i32 * 114) / 1000)
+        .collect()
+}
+
+/// How much detail each column (or row when not `columns`) has, measured as the
+/// change in brightness between neighbouring pixels.
+fn energy(img: &RgbaImage, columns: bool) -> Vec<u64> {
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    let luma = luma(img);
+    let mut energy = vec![0u64; if columns { width } else { height }];
+    for y in 0..height {
+        for x in 0..width {
+            let here = luma[y * width + x];
+            let mut change = 0;
+            if x + 1 < width {
+                change += (luma[y * width + x + 1] - here).abs();
+            }
+            if y + 1 < height {
+                change += (luma[(y + 1) * width + x] - here).abs();
+            }
+            energy[if columns { x } else { y }] += change as u64;
+        }
+    }
+    energy
+}
+
+/// Start of the `window` wide run of `energy` with the largest total. Ties go to
+/// the run nearest the middle.
+fn busiest_window(energy: &[u64], window: usize) -> usize {
+    if window >= energy.len() {
+        return 0;
+    }
+    let middle = (energy.len() - window) / 2;
+    let mut total: u64 = energy[..window].iter().sum();
+    let mut best = (total, 0);
+    for start in 1..=energy.len() - window {
+        total = total + energy[start + window - 1] - energy[start - 1];
+        let closer =
+            (start as isize - middle as isize).abs() < (best.1 as isize - middle as isize).abs();
+        if total > best.0 || (total == best.0 && closer) {
+            best = (total, start);
+        }
+    }
+    best.1
+}
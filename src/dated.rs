@@ -0,0 +1,65 @@
+use anyhow::{anyhow, Error, Result};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Most entries made in one second before giving up.
+const MAX_PER_SECOND: usize = 1000;
+
+/// Makes a new entry in `dir` named by `time`, with `create`, which must fail with
+/// `AlreadyExists` rather than reuse an entry. Names carry a counter so entries
+/// made in the same second don't collide, and still sort oldest first.
+pub fn create<T, F>(
+    dir: &Path,
+    time: u64,
+    extension: &str,
+    create: F,
+) -> Result<(PathBuf, T), Error>
+where
+    F: Fn(&Path) -> io::Result<T>,
+{
+    fs::create_dir_all(dir)?;
+    for count in 0..MAX_PER_SECOND {
+        let mut name = format!("{}-{:03}", time, count);
+        if !extension.is_empty() {
+            name = format!("{}.{}", name, extension);
+        }
+        let path = dir.join(name);
+        match create(&path) {
+            Ok(created) => return Ok((path, created)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Err(anyhow!("Too many entries in {} at once", dir.display()))
+}
+
+/// Entries of `dir` that `include` accepts, oldest first.
+pub fn list<F>(dir: &Path, include: F) -> Vec<PathBuf>
+where
+    F: Fn(&Path) -> bool,
+{
+    let mut entries: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| include(p))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    entries.sort();
+    entries
+}
+
+/// Removes all but the newest `keep` of `entries`, which are oldest first.
+pub fn prune<F>(mut entries: Vec<PathBuf>, keep: usize, remove: F)
+where
+    F: Fn(&Path) -> io::Result<()>,
+{
+    let excess = entries.len().saturating_sub(keep);
+    for entry in entries.drain(..excess) {
+        if let Err(err) = remove(&entry) {
+            eprintln!("Unable to remove {}: {}", entry.display(), err);
+        }
+    }
+}
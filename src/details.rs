@@ -0,0 +1,138 @@
+use crate::logs;
+use crate::LaunchStatus;
+use graphics::character::CharacterCache;
+use graphics::{math::Matrix2d, DrawState, Text, Transformed};
+use opengl_graphics::{GlGraphics, GlyphCache};
+use std::path::PathBuf;
+
+const OUTPUT_LINES: usize = 8;
+const FONT_SIZE: u32 = 10;
+const LINE_HEIGHT: f64 = 12.0;
+const MARGIN: f64 = 4.0;
+
+/// What was attempted for the last launch of a game, kept for the detail panel.
+pub struct LaunchDetails {
+    command_line: String,
+    /// Where the game's output goes; games handed to their store have none.
+    log: Option<PathBuf>,
+}
+
+impl LaunchDetails {
+    pub fn new(command_line: String, log: Option<PathBuf>) -> LaunchDetails {
+        LaunchDetails { command_line, log }
+    }
+
+    fn output_tail(&self) -> Vec<String> {
+        match &self.log {
+            Some(log) => logs::tail(log, OUTPUT_LINES),
+            None => Vec::new(),
+        }
+    }
+}
+
+fn status_text(status: Option<&LaunchStatus>) -> String {
+    match status {
+        None => "Not launched".to_owned(),
+        Some(LaunchStatus::Starting) => "Starting".to_owned(),
+        Some(LaunchStatus::Running) => "Running".to_owned(),
+        Some(LaunchStatus::Success) => "Exited normally".to_owned(),
+        Some(LaunchStatus::Stopped) => "Stopped".to_owned(),
+        Some(LaunchStatus::Managing(action)) => action.progress().to_owned(),
+        Some(LaunchStatus::Managed(message)) => message.clone(),
+        Some(LaunchStatus::Error(code)) => format!("Exited with code {}", code),
+        Some(LaunchStatus::FailedToLaunch(err)) => format!("Failed to launch: {}", err),
+        Some(LaunchStatus::Refused(reason)) => format!("Not launched: {}", reason),
+    }
+}
+
+/// Greedily breaks `text` into lines no wider than `width`.
+pub fn wrap(glyphs: &mut GlyphCache, size: u32, text: &str, width: f64) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for c in text.chars() {
+        line.push(c);
+        if glyphs.width(size, &line).unwrap_or(0.0) > width && line.chars().count() > 1 {
+            line.pop();
+            lines.push(line);
+            line = c.to_string();
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Writes `message` across the bottom of a tile, over a dark band.
+pub fn draw_message(
+    message: &str,
+    glyphs: &mut GlyphCache,
+    transform: Matrix2d,
+    gl: &mut GlGraphics,
+    width: usize,
+    height: usize,
+) {
+    let (width, height) = (width as f64, height as f64);
+    let lines = wrap(glyphs, FONT_SIZE, message, width - 2.0 * MARGIN);
+    let band = (lines.len() as f64 * LINE_HEIGHT + 2.0 * MARGIN).min(height);
+    let transform = transform.trans(0.0, height - band);
+    graphics::rectangle(
+        [0.0, 0.0, 0.0, 0.75],
+        [0.0, 0.0, width, band],
+        transform,
+        gl,
+    );
+
+    let state = DrawState::default();
+    let text = Text::new_color([1.0, 1.0, 1.0, 1.0], FONT_SIZE);
+    let mut y = MARGIN + FONT_SIZE as f64;
+    for line in lines {
+        if y > band {
+            return;
+        }
+        let _ = text.draw(&line, glyphs, &state, transform.trans(MARGIN, y), gl);
+        y += LINE_HEIGHT;
+    }
+}
+
+/// Covers a tile with the status, command line and latest output of its last launch.
+pub fn draw_details(
+    details: Option<&LaunchDetails>,
+    status: Option<&LaunchStatus>,
+    glyphs: &mut GlyphCache,
+    transform: Matrix2d,
+    gl: &mut GlGraphics,
+    width: usize,
+    height: usize,
+) {
+    let (width, height) = (width as f64, height as f64);
+    graphics::rectangle(
+        [0.0, 0.0, 0.0, 0.85],
+        [0.0, 0.0, width, height],
+        transform,
+        gl,
+    );
+
+    let mut paragraphs = vec![status_text(status)];
+    if let Some(details) = details {
+        paragraphs.push(details.command_line.clone());
+        let output = details.output_tail();
+        if !output.is_empty() {
+            paragraphs.push("output:".to_owned());
+            paragraphs.extend(output);
+        }
+    }
+
+    let state = DrawState::default();
+    let text = Text::new_color([1.0, 1.0, 1.0, 1.0], FONT_SIZE);
+    let mut y = MARGIN + FONT_SIZE as f64;
+    for paragraph in paragraphs {
+        for line in wrap(glyphs, FONT_SIZE, &paragraph, width - 2.0 * MARGIN) {
+            if y > height - MARGIN {
+                return;
+            }
+            let _ = text.draw(&line, glyphs, &state, transform.trans(MARGIN, y), gl);
+            y += LINE_HEIGHT;
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::watch::{steam_dir, steam_libraries};
+use crate::{Game, GameKey, Launcher};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Total size of the files under `path`. Symlinks aren't followed and anything
+/// unreadable counts as empty.
+pub fn dir_size(path: &Path) -> u64 {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => dir_size(&entry.path()),
+            Ok(kind) if kind.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Where Steam installed a game: `steamapps/common/<installdir>` in whichever
+/// library has its appmanifest.
+fn steam_install_dir(id: &str) -> Option<PathBuf> {
+    let steam = steam_dir()?;
+    steam_libraries(&steam).into_iter().find_map(|library| {
+        let steamapps = library.join("steamapps");
+        let manifest =
+            fs::read_to_string(steamapps.join(format!("appmanifest_{}.acf", id))).ok()?;
+        let dir = manifest.lines().find_map(|line| {
+            // `"installdir"<tabs>"Portal 2"`
+            let fields: Vec<&str> = line.split('"').collect();
+            match fields.as_slice() {
+                [_, "installdir", _, dir, _] => Some(dir.to_string()),
+                _ => None,
+            }
+        })?;
+        Some(steamapps.join("common").join(dir))
+    })
+}
+
+fn install_dir(game: &Game) -> Option<PathBuf> {
+    match (&game.install_directory, game.launcher) {
+        (Some(dir), _) => Some(PathBuf::from(dir)),
+        (None, Launcher::Steam) => steam_install_dir(&game.id),
+        (None, _) => None,
+    }
+}
+
+/// Size of an installed game, for games whose install folder can be found.
+pub fn install_size(game: &Game) -> Option<u64> {
+    if !game.installed {
+        return None;
+    }
+    let dir = install_dir(game)?;
+    if !dir.exists() {
+        return None;
+    }
+    Some(dir_size(&dir))
+}
+
+/// Measures installed games on a background thread, adding each to `sizes` as it is done.
+pub fn scan(games: &Vec<Game>, sizes: Arc<Mutex<HashMap<GameKey, u64>>>) {
+    let games: Vec<Game> = games.iter().filter(|g| g.installed).cloned().collect();
+    thread::spawn(move || {
+        for game in games {
+            if let Some(size) = install_size(&game) {
+                sizes.lock().unwrap().insert(game.key(), size);
+            }
+        }
+    });
+}
+
+/// Installed size per launcher, largest first.
+pub fn launcher_totals(games: &Vec<Game>) -> Vec<(Launcher, u64)> {
+    let mut totals: HashMap<Launcher, u64> = HashMap::new();
+    for game in games.iter().filter(|g| g.installed) {
+        if let Some(size) = game.install_size {
+            *totals.entry(game.launcher).or_insert(0) += size;
+        }
+    }
+    let mut totals: Vec<(Launcher, u64)> = totals.into_iter().collect();
+    totals.sort_by(|a, b| b.1.cmp(&a.1));
+    totals
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -0,0 +1,67 @@
+use anyhow::{Error, Result};
+use serde::Deserialize;
+use serde_json;
+use std::fs;
+use std::path::PathBuf;
+
+/// What the Epic Games Launcher records about each game it installed, in its
+/// `Data\Manifests\*.item` files.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Manifest {
+    pub display_name: String,
+    pub install_location: String,
+    pub app_name: String,
+    pub catalog_namespace: String,
+    pub catalog_item_id: String,
+}
+
+impl Manifest {
+    /// The id `com.epicgames.launcher://apps/` links take, already escaped.
+    pub fn store_id(&self) -> String {
+        format!(
+            "{}%3A{}%3A{}",
+            self.catalog_namespace, self.catalog_item_id, self.app_name
+        )
+    }
+
+    /// Whether this manifest is for the game installed in `location`.
+    pub fn installed_in(&self, location: &str) -> bool {
+        let normalize = |path: &str| {
+            path.replace('/', "\\")
+                .trim_end_matches('\\')
+                .to_lowercase()
+        };
+        normalize(&self.install_location) == normalize(location)
+    }
+}
+
+fn folder() -> PathBuf {
+    let data = std::env::var("ProgramData").unwrap_or_else(|_| r"C:\ProgramData".to_owned());
+    PathBuf::from(data).join(r"Epic\EpicGamesLauncher\Data\Manifests")
+}
+
+fn read(path: &PathBuf) -> Result<Manifest, Error> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Every manifest that can be read. There are none if the Epic launcher isn't
+/// installed, so its games just go without store ids.
+pub fn load() -> Vec<Manifest> {
+    let entries = match fs::read_dir(folder()) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "item"))
+        .filter_map(|path| match read(&path) {
+            Ok(manifest) => Some(manifest),
+            Err(err) => {
+                eprintln!("Unable to read {}: {}", path.display(), err);
+                None
+            }
+        })
+        .collect()
+}
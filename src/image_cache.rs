@@ -0,0 +1,365 @@
+use crate::sessions::now;
+use anyhow::{anyhow, Error, Result};
+use image::ImageFormat;
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{self, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use url::Url;
+
+const INDEX_JSON: &str = "index.json";
+/// How long a downloaded image is used before checking whether it changed.
+const REVALIDATE_AFTER: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct CachedImage {
+    /// Relative to the cache folder.
+    pub file: String,
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+    pub fetched: u64,
+}
+
+/// FNV-1a, as std's hasher isn't guaranteed to be stable between releases.
+pub fn hash(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Picks a file extension from the content type, then the contents, then the URL.
+pub fn extension(content_type: Option<&str>, contents: &[u8], url: &str) -> String {
+    let from_type = match content_type.map(|t| t.split(';').next().unwrap_or("").trim()) {
+        Some("image/png") => Some("png"),
+        Some("image/jpeg") | Some("image/jpg") => Some("jpg"),
+        Some("image/gif") => Some("gif"),
+        Some("image/webp") => Some("webp"),
+        Some("image/bmp") => Some("bmp"),
+        Some("image/x-icon") | Some("image/vnd.microsoft.icon") => Some("ico"),
+        _ => None,
+    };
+    let sniffed = || match image::guess_format(contents) {
+        Ok(ImageFormat::PNG) => Some("png"),
+        Ok(ImageFormat::JPEG) => Some("jpg"),
+        Ok(ImageFormat::GIF) => Some("gif"),
+        Ok(ImageFormat::WEBP) => Some("webp"),
+        Ok(ImageFormat::BMP) => Some("bmp"),
+        Ok(ImageFormat::ICO) => Some("ico"),
+        _ => None,
+    };
+    if let Some(ext) = from_type.or_else(sniffed) {
+        return ext.to_owned();
+    }
+    Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            let name = url.path_segments()?.last()?.to_owned();
+            let ext = name.rsplit('.').next()?.to_lowercase();
+            if name.contains('.') && ext.chars().all(|c| c.is_ascii_alphanumeric()) {
+                Some(ext)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| "img".to_owned())
+}
+
+/// Writes `contents` to `path` through a `.part` file, so an interrupted write
+/// never looks like a finished image.
+pub fn write_whole(path: &Path, contents: &[u8]) -> Result<(), Error> {
+    let partial = path.with_extension("part");
+    fs::write(&partial, contents)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+fn header(resp: &reqwest::Response, name: reqwest::header::HeaderName) -> Option<String> {
+    resp.headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_owned())
+}
+
+/// Downloaded images, named by a hash of their URL so covers that share a file
+/// name can't collide. `index.json` maps each URL to its file and validators.
+pub struct ImageCache {
+    folder: PathBuf,
+    index: Mutex<HashMap<String, CachedImage>>,
+}
+
+impl ImageCache {
+    pub fn load(folder: &PathBuf) -> ImageCache {
+        let path = folder.join(INDEX_JSON);
+        let index = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                eprintln!("Ignoring broken image index {}: {}", path.display(), err);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        ImageCache {
+            folder: folder.clone(),
+            index: Mutex::new(index),
+        }
+    }
+
+    fn save(&self, index: &HashMap<String, CachedImage>) {
+        let written = serde_json::to_string_pretty(index)
+            .map_err(Error::from)
+            .and_then(|json| Ok(fs::write(self.folder.join(INDEX_JSON), json)?));
+        if let Err(err) = written {
+            eprintln!("Unable to save image index: {}", err);
+        }
+    }
+
+    fn cached(&self, url: &str) -> Option<CachedImage> {
+        self.index
+            .lock()
+            .unwrap()
+            .get(url)
+            .cloned()
+            .filter(|c| self.folder.join(&c.file).exists())
+    }
+
+    /// Every cached URL and its file, which may have gone missing.
+    pub fn entries(&self) -> Vec<(String, PathBuf)> {
+        self.index
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(url, cached)| (url.clone(), self.folder.join(&cached.file)))
+            .collect()
+    }
+
+    /// Cached URLs whose file is missing, empty or doesn't decode, and what is wrong.
+    pub fn broken(&self) -> Vec<(String, String)> {
+        let mut broken: Vec<(String, String)> = self
+            .entries()
+            .into_iter()
+            .filter_map(|(url, path)| {
+                let problem = match fs::read(&path) {
+                    Err(err) => format!("unable to read {}: {}", path.display(), err),
+                    Ok(contents) if contents.is_empty() => "empty file".to_owned(),
+                    Ok(contents) => match image::load_from_memory(&contents) {
+                        Ok(_) => return None,
+                        Err(err) => format!("doesn't decode: {}", err),
+                    },
+                };
+                Some((url, problem))
+            })
+            .collect();
+        broken.sort();
+        broken
+    }
+
+    /// Drops `url` and its file so the next fetch downloads it again.
+    pub fn forget(&self, url: &str) {
+        let mut index = self.index.lock().unwrap();
+        if let Some(cached) = index.remove(url) {
+            let _ = fs::remove_file(self.folder.join(cached.file));
+            self.save(&index);
+        }
+    }
+
+    /// Removes URLs not in `keep`, and any file the index doesn't know about such
+    /// as images saved before it existed or interrupted downloads. Returns the
+    /// number of files removed and their size.
+    pub fn prune(&self, keep: &HashSet<String>) -> Result<(usize, u64), Error> {
+        let mut index = self.index.lock().unwrap();
+        index.retain(|url, _| keep.contains(url));
+        self.save(&index);
+        let known: HashSet<&str> = index.values().map(|c| c.file.as_str()).collect();
+        let mut removed = (0, 0);
+        for entry in fs::read_dir(&self.folder)?.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == INDEX_JSON || known.contains(name.as_str()) || !entry.path().is_file() {
+                continue;
+            }
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            fs::remove_file(entry.path())?;
+            removed.0 += 1;
+            removed.1 += size;
+        }
+        Ok(removed)
+    }
+
+    /// The downloaded file for `url`, if there is one.
+    pub fn path(&self, url: &str) -> Option<PathBuf> {
+        self.cached(url).map(|c| self.folder.join(c.file))
+    }
+
+    fn update(&self, url: &str, entry: CachedImage) {
+        let mut index = self.index.lock().unwrap();
+        index.insert(url.to_owned(), entry);
+        self.save(&index);
+    }
+
+    /// Returns the file for `url`, downloading it if it isn't cached and checking
+    /// whether it changed once it is old enough. A cached file is used if that check fails.
+    pub fn fetch(&self, client: &reqwest::Client, url: &str) -> Result<PathBuf, Error> {
+        let cached = self.cached(url);
+        if let Some(cached) = &cached {
+            if now().saturating_sub(cached.fetched) < REVALIDATE_AFTER {
+                return Ok(self.folder.join(&cached.file));
+            }
+        }
+
+        let mut request = client.get(url);
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header(IF_NONE_MATCH, etag.as_str());
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified.as_str());
+            }
+        }
+        let response = request
+            .send()
+            .map_err(Error::from)
+            .and_then(|resp| match resp.status() {
+                status if status.is_success() || status == StatusCode::NOT_MODIFIED => Ok(resp),
+                status => Err(anyhow!("{} returned {}", url, status)),
+            });
+        let mut resp = match (response, cached) {
+            (Ok(resp), _) => resp,
+            (Err(err), Some(cached)) => {
+                eprintln!("Unable to revalidate {}: {}", url, err);
+                return Ok(self.folder.join(&cached.file));
+            }
+            (Err(err), None) => return Err(err),
+        };
+
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            let mut cached = self
+                .cached(url)
+                .ok_or_else(|| anyhow!("{} is no longer cached", url))?;
+            cached.fetched = now();
+            let path = self.folder.join(&cached.file);
+            self.update(url, cached);
+            return Ok(path);
+        }
+
+        let mut contents = Vec::new();
+        resp.read_to_end(&mut contents)?;
+        let ext = extension(header(&resp, CONTENT_TYPE).as_deref(), &contents, url);
+        let file = format!("{}.{}", hash(url.as_bytes()), ext);
+        let path = self.folder.join(&file);
+        write_whole(&path, &contents)?;
+        if let Some(old) = self.cached(url).filter(|old| old.file != file) {
+            let _ = fs::remove_file(self.folder.join(old.file));
+        }
+        self.update(
+            url,
+            CachedImage {
+                file,
+                etag: header(&resp, ETAG),
+                last_modified: header(&resp, LAST_MODIFIED),
+                fetched: now(),
+            },
+        );
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{response, temp_folder, TestServer};
+
+    /// Makes the cached copy of `url` old enough to be checked again.
+    fn expire(cache: &ImageCache, url: &str) {
+        let mut cached = cache.cached(url).unwrap();
+        cached.fetched = 0;
+        cache.update(url, cached);
+    }
+
+    #[test]
+    fn revalidates_stale_images_with_their_etag() {
+        let server = TestServer::start(vec![
+            response(
+                "200 OK",
+                &[("Content-Type", "image/png"), ("ETag", "\"v1\"")],
+                b"cover",
+            ),
+            response("304 Not Modified", &[("ETag", "\"v1\"")], b""),
+        ]);
+        let folder = temp_folder("revalidate");
+        let cache = ImageCache::load(&folder);
+        let client = reqwest::Client::new();
+        let url = server.url("cover.png");
+
+        let path = cache.fetch(&client, &url).unwrap();
+        assert_eq!(path, folder.join(format!("{}.png", hash(url.as_bytes()))));
+        // A fresh copy is used without asking the server.
+        assert_eq!(cache.fetch(&client, &url).unwrap(), path);
+        assert_eq!(server.requests().len(), 1);
+
+        expire(&cache, &url);
+        assert_eq!(cache.fetch(&client, &url).unwrap(), path);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(!requests[0].contains("if-none-match"));
+        assert!(requests[1].contains("if-none-match: \"v1\""));
+        assert_eq!(fs::read(&path).unwrap(), b"cover");
+        assert!(now() - cache.cached(&url).unwrap().fetched < REVALIDATE_AFTER);
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn replaces_images_that_changed() {
+        let server = TestServer::start(vec![
+            response(
+                "200 OK",
+                &[("Content-Type", "image/png"), ("ETag", "\"v1\"")],
+                b"old",
+            ),
+            response(
+                "200 OK",
+                &[("Content-Type", "image/jpeg"), ("ETag", "\"v2\"")],
+                b"new",
+            ),
+        ]);
+        let folder = temp_folder("replace");
+        let cache = ImageCache::load(&folder);
+        let client = reqwest::Client::new();
+        let url = server.url("cover");
+
+        let old = cache.fetch(&client, &url).unwrap();
+        expire(&cache, &url);
+        let new = cache.fetch(&client, &url).unwrap();
+        assert_ne!(new, old);
+        assert!(!old.exists());
+        assert_eq!(fs::read(&new).unwrap(), b"new");
+        assert_eq!(cache.cached(&url).unwrap().etag.as_deref(), Some("\"v2\""));
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn keeps_the_cached_image_when_revalidation_fails() {
+        let server = TestServer::start(vec![
+            response("200 OK", &[("Content-Type", "image/png")], b"cover"),
+            response("500 Internal Server Error", &[], b""),
+        ]);
+        let folder = temp_folder("keep");
+        let cache = ImageCache::load(&folder);
+        let client = reqwest::Client::new();
+        let url = server.url("cover.png");
+
+        let path = cache.fetch(&client, &url).unwrap();
+        expire(&cache, &url);
+        assert_eq!(cache.fetch(&client, &url).unwrap(), path);
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(fs::read(&path).unwrap(), b"cover");
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
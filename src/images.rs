@@ -0,0 +1,471 @@
+use crate::artwork::Artwork;
+use crate::crop::Crop;
+use crate::image_cache::ImageCache;
+use crate::thumbnails::Thumbnails;
+use crate::{ArtKind, Game, ImageSource, MAX_TILE_HEIGHT, MAX_TILE_WIDTH};
+use anyhow::{anyhow, Error, Result};
+use image::{AnimationDecoder, ImageFormat, RgbaImage};
+use notify::{recommended_watcher, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use opengl_graphics::{Texture, TextureSettings};
+use reqwest;
+use std::cell::{Cell, OnceCell};
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, sleep};
+use std::time::{Duration, Instant};
+
+/// Fewest downloads and decodes running at once; more on machines with more cores.
+const MIN_WORKERS: usize = 4;
+const ATTEMPTS: u32 = 4;
+/// Wait before the first retry; doubled after each failure.
+#[cfg(not(test))]
+const BACKOFF: Duration = Duration::from_secs(1);
+#[cfg(test)]
+const BACKOFF: Duration = Duration::from_millis(10);
+/// Used for frames that ask for no delay at all, as browsers do.
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+/// Frames kept of an animated image; longer ones are cut short to bound memory.
+const MAX_FRAMES: usize = 100;
+
+fn fetch_with_retries(
+    cache: &ImageCache,
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<PathBuf, Error> {
+    let mut delay = BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match cache.fetch(client, url) {
+            Ok(path) => return Ok(path),
+            Err(err) if attempt < ATTEMPTS => {
+                eprintln!(
+                    "Unable to download {}: {}; retrying in {:?}",
+                    url, err, delay
+                );
+                sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Decodes an image, crops it if asked and shrinks it to tile size to reduce
+/// GPU memory consumption.
+pub fn thumbnail(contents: &[u8], crop: Option<Crop>) -> Result<RgbaImage, Error> {
+    let img = match image::load_from_memory(contents)? {
+        image::DynamicImage::ImageRgba8(img) => img,
+        x => x.to_rgba(),
+    };
+    Ok(fit_tile(img, crop))
+}
+
+fn fit_tile(img: RgbaImage, crop: Option<Crop>) -> RgbaImage {
+    let img = match crop {
+        Some(crop) => crop.apply(&img),
+        None => img,
+    };
+    let scale = f32::min(
+        MAX_TILE_WIDTH as f32 / img.width() as f32,
+        MAX_TILE_HEIGHT as f32 / img.height() as f32,
+    );
+    image::imageops::resize(
+        &img,
+        (img.width() as f32 * scale) as u32,
+        (img.height() as f32 * scale) as u32,
+        image::imageops::FilterType::Gaussian,
+    )
+}
+
+pub struct Frame {
+    pub image: RgbaImage,
+    pub delay: Duration,
+}
+
+/// The first `MAX_FRAMES` frames of an animated image at tile size, or nothing
+/// if it isn't animated. Only GIFs are; the image crate in use can't animate
+/// APNG or WebP, so those show their first frame like any other image.
+pub fn frames(contents: &[u8], crop: Option<Crop>) -> Result<Vec<Frame>, Error> {
+    if image::guess_format(contents)? != ImageFormat::GIF {
+        return Ok(Vec::new());
+    }
+    // Each frame is shrunk as it is decoded, so only one is ever held at full size.
+    let frames = image::gif::Decoder::new(Cursor::new(contents))?
+        .into_frames()
+        .take(MAX_FRAMES)
+        .map(|frame| {
+            let frame = frame?;
+            // Milliseconds.
+            let delay = match frame.delay().to_integer() {
+                0 => DEFAULT_FRAME_DELAY,
+                ms => Duration::from_millis(ms as u64),
+            };
+            Ok(Frame {
+                image: fit_tile(frame.into_buffer(), crop),
+                delay,
+            })
+        })
+        .collect::<Result<Vec<Frame>, Error>>()?;
+    if frames.len() < 2 {
+        return Ok(Vec::new());
+    }
+    Ok(frames)
+}
+
+/// Where a game's image is read from, once it has been downloaded. A local
+/// override beats the images the game's source provides.
+pub fn source_path(
+    cache: &ImageCache,
+    artwork: &Artwork,
+    preferred: &[ArtKind],
+    game: &Game,
+) -> Option<PathBuf> {
+    if let Some(path) = artwork.find(game) {
+        return Some(path);
+    }
+    game.image_sources(preferred)
+        .iter()
+        .find_map(|source| match source {
+            ImageSource::Url(url) => cache.path(url),
+            ImageSource::Path(path) => Some(PathBuf::from(path)).filter(|p| p.is_file()),
+            ImageSource::None => None,
+        })
+}
+
+fn load_source(
+    cache: &ImageCache,
+    thumbnails: &Thumbnails,
+    client: &reqwest::Client,
+    source: &ImageSource,
+    crop: Option<Crop>,
+) -> Result<RgbaImage, Error> {
+    let path = match source {
+        ImageSource::Url(url) => fetch_with_retries(cache, client, url)?,
+        ImageSource::Path(path) => PathBuf::from(path),
+        ImageSource::None => return Err(anyhow!("No image")),
+    };
+    thumbnails.get(&fs::read(&path)?, crop)
+}
+
+/// The contents of the first of the game's images that can be read, in order
+/// of preference, downloading it if need be.
+fn contents(
+    cache: &ImageCache,
+    artwork: &Artwork,
+    preferred: &[ArtKind],
+    client: &reqwest::Client,
+    game: &Game,
+) -> Result<Vec<u8>, Error> {
+    if let Some(path) = artwork.find(game) {
+        return Ok(fs::read(&path)?);
+    }
+    let mut last_err = anyhow!("{} has no artwork", game.title);
+    for source in game.image_sources(preferred) {
+        let path = match &source {
+            ImageSource::Url(url) => fetch_with_retries(cache, client, url),
+            ImageSource::Path(path) => Ok(PathBuf::from(path)),
+            ImageSource::None => continue,
+        };
+        match path.and_then(|path| Ok(fs::read(&path)?)) {
+            Ok(contents) => return Ok(contents),
+            Err(err) => last_err = err,
+        }
+    }
+    Err(last_err)
+}
+
+/// Loads the first of the game's images that can be, in order of preference.
+fn load(
+    cache: &ImageCache,
+    thumbnails: &Thumbnails,
+    artwork: &Artwork,
+    preferred: &[ArtKind],
+    client: &reqwest::Client,
+    game: &Game,
+    crop: Option<Crop>,
+) -> Result<RgbaImage, Error> {
+    if let Some(path) = artwork.find(game) {
+        return thumbnails.get(&fs::read(&path)?, crop);
+    }
+    let mut last_err = anyhow!("{} has no artwork", game.title);
+    for source in game.image_sources(preferred) {
+        match load_source(cache, thumbnails, client, &source, crop) {
+            Ok(img) => return Ok(img),
+            Err(err) => last_err = err,
+        }
+    }
+    Err(last_err)
+}
+
+/// Sends every path that changes in `folder`, so overrides put in place by
+/// `art set` or by hand show without a restart.
+fn watch(folder: &PathBuf, changed: mpsc::Sender<PathBuf>) -> Result<RecommendedWatcher, Error> {
+    fs::create_dir_all(folder)?;
+    let mut watcher = recommended_watcher(move |event: notify::Result<Event>| match event {
+        Ok(event) => {
+            for path in event.paths {
+                let _ = changed.send(path);
+            }
+        }
+        Err(err) => eprintln!("Error watching artwork: {}", err),
+    })?;
+    watcher.watch(folder, RecursiveMode::Recursive)?;
+    Ok(watcher)
+}
+
+struct ImageJob {
+    index: usize,
+    game: Game,
+    crop: Option<Crop>,
+    /// Decode every frame of the image rather than its thumbnail.
+    animate: bool,
+}
+
+pub struct LoadedImage {
+    /// Index of the game in `Doorways::games` when it was requested.
+    pub index: usize,
+    /// What the image was cropped to, in case tiles have changed shape since.
+    pub crop: Option<Crop>,
+    pub image: Result<RgbaImage, Error>,
+}
+
+pub struct LoadedFrames {
+    pub index: usize,
+    pub crop: Option<Crop>,
+    pub frames: Result<Vec<Frame>, Error>,
+}
+
+/// Downloads and decodes images on a small pool of background threads. Textures
+/// still have to be created on the thread that owns the GL context.
+pub struct ImageLoader {
+    jobs: mpsc::Sender<ImageJob>,
+    pub loaded: mpsc::Receiver<LoadedImage>,
+    pub animated: mpsc::Receiver<LoadedFrames>,
+    /// Files in the artwork folder added, changed or removed since it was started.
+    pub artwork_changed: mpsc::Receiver<PathBuf>,
+    /// Feeds `artwork_changed`; none if the artwork folder couldn't be watched.
+    _artwork_watcher: Option<RecommendedWatcher>,
+    cache: Arc<ImageCache>,
+    artwork: Arc<Artwork>,
+    preferred: Arc<Vec<ArtKind>>,
+}
+
+impl ImageLoader {
+    pub fn new(
+        folder: &PathBuf,
+        thumbnail_folder: &PathBuf,
+        artwork_folder: &PathBuf,
+        preferred: &[ArtKind],
+    ) -> ImageLoader {
+        if let Err(err) = fs::create_dir_all(folder) {
+            eprintln!("Unable to create {}: {}", folder.display(), err);
+        }
+        let cache = Arc::new(ImageCache::load(folder));
+        let thumbnails = Arc::new(Thumbnails::new(thumbnail_folder));
+        let artwork = Arc::new(Artwork::new(artwork_folder));
+        let preferred = Arc::new(preferred.to_vec());
+        let workers = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .max(MIN_WORKERS);
+        let (jobs, queue) = mpsc::channel::<ImageJob>();
+        let queue = Arc::new(Mutex::new(queue));
+        let (done, loaded) = mpsc::channel();
+        let (done_animating, animated) = mpsc::channel();
+        for _ in 0..workers {
+            let queue = queue.clone();
+            let done = done.clone();
+            let done_animating = done_animating.clone();
+            let cache = cache.clone();
+            let thumbnails = thumbnails.clone();
+            let artwork = artwork.clone();
+            let preferred = preferred.clone();
+            thread::spawn(move || {
+                let client = reqwest::Client::new();
+                loop {
+                    // The lock is only held while waiting for the next job.
+                    let job = match queue.lock().unwrap().recv() {
+                        Ok(job) => job,
+                        Err(_) => return,
+                    };
+                    if job.animate {
+                        let decoded = contents(&cache, &artwork, &preferred, &client, &job.game)
+                            .and_then(|contents| frames(&contents, job.crop));
+                        let loaded = LoadedFrames {
+                            index: job.index,
+                            crop: job.crop,
+                            frames: decoded,
+                        };
+                        if done_animating.send(loaded).is_err() {
+                            return;
+                        }
+                        continue;
+                    }
+                    let image = load(
+                        &cache,
+                        &thumbnails,
+                        &artwork,
+                        &preferred,
+                        &client,
+                        &job.game,
+                        job.crop,
+                    );
+                    let loaded = LoadedImage {
+                        index: job.index,
+                        crop: job.crop,
+                        image,
+                    };
+                    if done.send(loaded).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        let (changed, artwork_changed) = mpsc::channel();
+        let artwork_watcher = match watch(artwork_folder, changed) {
+            Ok(watcher) => Some(watcher),
+            Err(err) => {
+                eprintln!("Not watching {}: {}", artwork_folder.display(), err);
+                None
+            }
+        };
+        ImageLoader {
+            jobs,
+            loaded,
+            animated,
+            artwork_changed,
+            _artwork_watcher: artwork_watcher,
+            cache,
+            artwork,
+            preferred,
+        }
+    }
+
+    pub fn source_path(&self, game: &Game) -> Option<PathBuf> {
+        source_path(&self.cache, &self.artwork, &self.preferred, game)
+    }
+
+    pub fn request(&self, index: usize, game: &Game, crop: Option<Crop>) {
+        // Workers only stop once the loader is dropped, so this can't fail.
+        let _ = self.jobs.send(ImageJob {
+            index,
+            game: game.clone(),
+            crop,
+            animate: false,
+        });
+    }
+
+    /// Decodes the frames of the game's image, downloading it first if need be.
+    pub fn request_frames(&self, index: usize, game: &Game, crop: Option<Crop>) {
+        let _ = self.jobs.send(ImageJob {
+            index,
+            game: game.clone(),
+            crop,
+            animate: true,
+        });
+    }
+}
+
+/// A game's texture. It is only uploaded once its tile comes into view and is
+/// dropped again when it hasn't been drawn for a while.
+#[derive(Default)]
+pub struct TileImage {
+    pub texture: OnceCell<Texture>,
+    /// Queued with the loader, or already loaded.
+    pub requested: Cell<bool>,
+    /// When the tile was last drawn, in draws since the launcher started.
+    pub last_drawn: Cell<u64>,
+}
+
+/// The frames of the one tile being animated. The rest only have a still texture,
+/// which keeps GPU usage down.
+pub struct Animation {
+    pub index: usize,
+    /// Empty until loaded, and for images that aren't animated.
+    pub frames: Vec<(Texture, Duration)>,
+    started: Instant,
+}
+
+impl Animation {
+    pub fn new(index: usize) -> Animation {
+        Animation {
+            index,
+            frames: Vec::new(),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn start(&mut self, frames: Vec<Frame>) {
+        self.frames = frames
+            .into_iter()
+            .map(|f| {
+                (
+                    Texture::from_image(&f.image, &TextureSettings::new()),
+                    f.delay,
+                )
+            })
+            .collect();
+        self.started = Instant::now();
+    }
+
+    /// The frame to show now, looping forever.
+    pub fn frame(&self) -> Option<&Texture> {
+        let total: Duration = self.frames.iter().map(|(_texture, delay)| *delay).sum();
+        if total == Duration::from_secs(0) {
+            return None;
+        }
+        let total = total.as_millis();
+        let mut at = self.started.elapsed().as_millis() % total;
+        for (texture, delay) in &self.frames {
+            if at < delay.as_millis() {
+                return Some(texture);
+            }
+            at -= delay.as_millis();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{response, temp_folder, TestServer};
+
+    #[test]
+    fn retries_failed_downloads_with_backoff() {
+        let server = TestServer::start(vec![
+            response("500 Internal Server Error", &[], b""),
+            response("503 Service Unavailable", &[], b""),
+            response("200 OK", &[("Content-Type", "image/png")], b"cover"),
+        ]);
+        let folder = temp_folder("retries");
+        let cache = ImageCache::load(&folder);
+        let started = Instant::now();
+        let path =
+            fetch_with_retries(&cache, &reqwest::Client::new(), &server.url("cover.png")).unwrap();
+        // Waited once for the first failure, then twice as long for the second.
+        assert!(started.elapsed() >= BACKOFF * 3);
+        assert_eq!(server.requests().len(), 3);
+        assert_eq!(fs::read(&path).unwrap(), b"cover");
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn gives_up_after_every_attempt_fails() {
+        let server = TestServer::start(
+            (0..ATTEMPTS)
+                .map(|_| response("500 Internal Server Error", &[], b""))
+                .collect(),
+        );
+        let folder = temp_folder("give-up");
+        let cache = ImageCache::load(&folder);
+        let url = server.url("cover.png");
+        assert!(fetch_with_retries(&cache, &reqwest::Client::new(), &url).is_err());
+        assert_eq!(server.requests().len(), ATTEMPTS as usize);
+        assert!(cache.path(&url).is_none());
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
@@ -0,0 +1,204 @@
+use crate::process;
+use crate::sources::Source;
+use anyhow::{anyhow, Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json;
+use std::collections::hash_map::RandomState;
+use std::fs::{self, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::thread::{self, sleep};
+use std::time::Duration;
+
+const LOCK_FILE: &str = "doorways.lock";
+
+/// Requests a second invocation forwards to the running launcher.
+#[derive(Deserialize, Serialize, Debug)]
+pub enum IpcCommand {
+    Focus,
+    Launch(String),
+    Refresh(Vec<Source>),
+    /// Stops a game the launcher is watching, so it records the session as stopped.
+    Stop(String),
+}
+
+/// A command with the token from the lock file, which only processes that can
+/// read it know, so other local programs can't drive the launcher.
+#[derive(Deserialize, Serialize)]
+struct Request {
+    token: String,
+    command: IpcCommand,
+}
+
+#[derive(Deserialize, Serialize)]
+struct LockContents {
+    pid: u32,
+    port: u16,
+    token: String,
+}
+
+/// How to reach the running launcher.
+pub struct Peer {
+    pub pid: u32,
+    port: u16,
+    token: String,
+}
+
+/// Held by the one doorways allowed to show the launcher; removed on drop.
+pub struct InstanceLock {
+    path: PathBuf,
+    listener: Option<TcpListener>,
+    token: String,
+}
+
+pub enum Instance {
+    Primary(InstanceLock),
+    /// Another launcher is running.
+    Secondary(Peer),
+}
+
+/// 128 random bits. Each `RandomState` is seeded from the system's random source.
+fn new_token() -> String {
+    (0..2)
+        .map(|_| format!("{:016x}", RandomState::new().build_hasher().finish()))
+        .collect()
+}
+
+fn read_lock(path: &PathBuf) -> Option<LockContents> {
+    // The owner writes the file right after creating it, so give it a moment.
+    for _ in 0..5 {
+        if let Ok(contents) = fs::read_to_string(path) {
+            if let Ok(lock) = serde_json::from_str(&contents) {
+                return Some(lock);
+            }
+        }
+        sleep(Duration::from_millis(100));
+    }
+    None
+}
+
+fn address(port: u16) -> SocketAddr {
+    SocketAddr::from((Ipv4Addr::LOCALHOST, port))
+}
+
+/// The running primary instance, if there is one.
+pub fn running(cache_dir: &PathBuf) -> Option<Peer> {
+    let path = cache_dir.join(LOCK_FILE);
+    if !path.exists() {
+        return None;
+    }
+    read_lock(&path)
+        .filter(|lock| process::is_alive(lock.pid))
+        .filter(|lock| TcpStream::connect(address(lock.port)).is_ok())
+        .map(|lock| Peer {
+            pid: lock.pid,
+            port: lock.port,
+            token: lock.token,
+        })
+}
+
+/// Fails while a launcher is running, for commands that rewrite files it keeps
+/// using, so neither overwrites the other's changes.
+pub fn ensure_not_running(cache_dir: &PathBuf, what: &str) -> Result<(), Error> {
+    if running(cache_dir).is_some() {
+        return Err(anyhow!(
+            "Close the launcher before {}; it is using the same files",
+            what
+        ));
+    }
+    Ok(())
+}
+
+impl Instance {
+    /// Becomes the primary instance unless a live one already holds the lock.
+    pub fn acquire(cache_dir: &PathBuf) -> Result<Instance, Error> {
+        let path = cache_dir.join(LOCK_FILE);
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    let listener = TcpListener::bind(address(0))?;
+                    let contents = LockContents {
+                        pid: std::process::id(),
+                        port: listener.local_addr()?.port(),
+                        token: new_token(),
+                    };
+                    file.write_all(serde_json::to_string(&contents)?.as_bytes())?;
+                    return Ok(Instance::Primary(InstanceLock {
+                        path,
+                        listener: Some(listener),
+                        token: contents.token,
+                    }));
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                    if let Some(peer) = running(cache_dir) {
+                        return Ok(Instance::Secondary(peer));
+                    }
+                    eprintln!("Removing stale lock {}", path.display());
+                    fs::remove_file(&path)?;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Err(anyhow!("Unable to acquire {}", path.display()))
+    }
+}
+
+impl InstanceLock {
+    /// Hands every command sent by later invocations to `handler` on a background thread.
+    pub fn listen<F>(&mut self, handler: F)
+    where
+        F: Fn(IpcCommand) + Send + 'static,
+    {
+        let listener = match self.listener.take() {
+            Some(listener) => listener,
+            None => return,
+        };
+        let token = self.token.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        eprintln!("Unable to accept connection: {}", err);
+                        continue;
+                    }
+                };
+                let mut line = String::new();
+                if let Err(err) = BufReader::new(stream).read_line(&mut line) {
+                    eprintln!("Unable to read command: {}", err);
+                    continue;
+                }
+                // `running` connects without sending anything to check we're alive.
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<Request>(&line) {
+                    Ok(request) if request.token == token => handler(request.command),
+                    Ok(_) => eprintln!("Ignoring command without the lock file's token"),
+                    Err(err) => eprintln!("Ignoring unknown command {:?}: {}", line, err),
+                }
+            }
+        });
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            eprintln!("Unable to remove {}: {}", self.path.display(), err);
+        }
+    }
+}
+
+/// Sends a command to the primary instance.
+pub fn forward(peer: &Peer, command: IpcCommand) -> Result<(), Error> {
+    let mut stream = TcpStream::connect(address(peer.port))?;
+    let request = Request {
+        token: peer.token.clone(),
+        command,
+    };
+    writeln!(stream, "{}", serde_json::to_string(&request)?)?;
+    Ok(())
+}
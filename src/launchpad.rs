@@ -0,0 +1,212 @@
+use crate::config::Limits;
+use crate::details::LaunchDetails;
+use crate::logs;
+use crate::monitor::{add_running_on_steam, Launched, MonitorMsg};
+use crate::sessions::{now, RunningGame};
+use crate::store::{StoreAction, StoreCommand};
+use crate::{Game, GameKey, LaunchStatus};
+use anyhow::{anyhow, Error, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+/// Everything needed to launch a game and hand it to the monitor. Cheap to
+/// clone so launches can also come from outside the UI thread.
+#[derive(Clone)]
+pub struct Launchpad {
+    status: Arc<Mutex<HashMap<GameKey, LaunchStatus>>>,
+    details: Arc<Mutex<HashMap<GameKey, LaunchDetails>>>,
+    monitor: mpsc::Sender<MonitorMsg>,
+    cache_dir: PathBuf,
+    limits: Limits,
+}
+
+impl Launchpad {
+    pub fn new(
+        status: Arc<Mutex<HashMap<GameKey, LaunchStatus>>>,
+        details: Arc<Mutex<HashMap<GameKey, LaunchDetails>>>,
+        monitor: mpsc::Sender<MonitorMsg>,
+        cache_dir: PathBuf,
+        limits: Limits,
+    ) -> Launchpad {
+        Launchpad {
+            status,
+            details,
+            monitor,
+            cache_dir,
+            limits,
+        }
+    }
+
+    pub fn send(&self, msg: MonitorMsg) -> Result<(), Error> {
+        self.monitor
+            .send(msg)
+            .map_err(|_| anyhow!("Status thread is no longer running"))
+    }
+
+    /// Launches `games[i]` unless it is already running or a limit has been reached.
+    /// Games that aren't installed are handed to their store to install instead.
+    pub fn launch(&self, games: &Vec<Game>, i: usize) {
+        let game = &games[i];
+        if !game.installed {
+            self.manage(game, StoreAction::Install);
+            return;
+        }
+        let key = game.key();
+        {
+            let mut status = self.status.lock().unwrap();
+            // Explicitly enumerating to ensure how each case is handled makes sense.
+            // If we are starting or the game is running, don't attempt to launch again.
+            // A store action may have been cancelled in the store, and nothing says
+            // so until a refresh, so it doesn't stop the game from launching.
+            match status.get(&key) {
+                Some(LaunchStatus::Starting) | Some(LaunchStatus::Running) => return,
+                None
+                | Some(LaunchStatus::Managing(_))
+                | Some(LaunchStatus::Error(_))
+                | Some(LaunchStatus::FailedToLaunch(_))
+                | Some(LaunchStatus::Stopped)
+                | Some(LaunchStatus::Refused(_))
+                | Some(LaunchStatus::Managed(_))
+                | Some(LaunchStatus::Success) => {}
+            };
+            let mut running: Vec<&Game> = games
+                .iter()
+                .filter(|g| match status.get(&g.key()) {
+                    Some(LaunchStatus::Starting) | Some(LaunchStatus::Running) => true,
+                    _ => false,
+                })
+                .collect();
+            add_running_on_steam(games, &mut running);
+            if let Some(reason) = self.limits.check(game, &running) {
+                status.insert(key, LaunchStatus::Refused(reason));
+                return;
+            }
+            status.insert(key.clone(), LaunchStatus::Starting);
+        }
+
+        if let Err(err) = self.spawn(game, &key) {
+            self.status
+                .lock()
+                .unwrap()
+                .insert(key, LaunchStatus::FailedToLaunch(err));
+        }
+    }
+
+    /// Has the game's store install, uninstall or verify it, reporting progress
+    /// through the game's status.
+    pub fn manage(&self, game: &Game, action: StoreAction) {
+        let key = game.key();
+        let mut status = self.status.lock().unwrap();
+        match status.get(&key) {
+            Some(LaunchStatus::Starting) | Some(LaunchStatus::Running) => return,
+            _ => {}
+        }
+        let StoreCommand { mut command, waits } = match game.store_command(action) {
+            Ok(store) => store,
+            Err(err) => {
+                status.insert(key, LaunchStatus::FailedToLaunch(err));
+                return;
+            }
+        };
+        // A URL handed to a store client is sent again: the store just shows it, and
+        // if it was cancelled there this is the way to start it over. A command we
+        // are waiting on clears the state itself once it is done.
+        if let (true, Some(LaunchStatus::Managing(_))) = (waits, status.get(&key)) {
+            return;
+        }
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                let err = anyhow!("Unable to {}: {}", action.name(), err);
+                status.insert(key, LaunchStatus::FailedToLaunch(err));
+                return;
+            }
+        };
+        if !waits {
+            // The store client carries on without us. A refresh will see an install
+            // or uninstall finish, but nothing shows when verifying is done.
+            let next = match action {
+                StoreAction::Verify => LaunchStatus::Managed("Verifying in the store".to_owned()),
+                _ => LaunchStatus::Managing(action),
+            };
+            status.insert(key, next);
+            return;
+        }
+        status.insert(key.clone(), LaunchStatus::Managing(action));
+        let statuses = self.status.clone();
+        thread::spawn(move || {
+            let result = match child.wait() {
+                Ok(exit) if exit.success() => LaunchStatus::Managed(action.done().to_owned()),
+                Ok(exit) => {
+                    LaunchStatus::FailedToLaunch(anyhow!("Unable to {}: {}", action.name(), exit))
+                }
+                Err(err) => {
+                    LaunchStatus::FailedToLaunch(anyhow!("Unable to {}: {}", action.name(), err))
+                }
+            };
+            statuses.lock().unwrap().insert(key, result);
+        });
+    }
+
+    fn spawn(&self, game: &Game, key: &GameKey) -> Result<(), Error> {
+        let mut command = game.launch_command()?;
+        let started = now();
+        // Games handed to their store through a URL write nothing of their own.
+        let log = if game.launches_directly() {
+            match logs::create(&self.cache_dir, key, started) {
+                Ok(log) => Some(log),
+                Err(err) => {
+                    eprintln!("Unable to create log for {}: {}", key.id, err);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        // The console is hidden, so output goes to the log. The game gets the file
+        // itself rather than a pipe, which would break once the launcher exits.
+        match &log {
+            Some((_path, file)) => {
+                command
+                    .stdout(Stdio::from(file.try_clone()?))
+                    .stderr(Stdio::from(file.try_clone()?));
+            }
+            None => {
+                command.stdout(Stdio::null()).stderr(Stdio::null());
+            }
+        }
+        let log = log.map(|(path, _file)| path);
+        let details = LaunchDetails::new(format!("{:?}", command), log.clone());
+        self.details.lock().unwrap().insert(key.clone(), details);
+        let child = match command.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                // Otherwise the empty log would pass for the latest session's.
+                if let Some(log) = &log {
+                    let _ = fs::remove_file(log);
+                }
+                return Err(err.into());
+            }
+        };
+        self.send(MonitorMsg::Launch(Launched::new(child, game, started, log)))
+    }
+
+    pub fn stop(&self, game: &Game) -> Result<(), Error> {
+        // Only the process that handed the game to its store is known.
+        if !game.launches_directly() {
+            return Err(anyhow!(
+                "started through {:?}, quit it in the game",
+                game.launcher
+            ));
+        }
+        self.send(MonitorMsg::Stop(game.key()))
+    }
+
+    pub fn reattach(&self, running: RunningGame) -> Result<(), Error> {
+        self.send(MonitorMsg::Reattach(running))
+    }
+}
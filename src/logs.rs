@@ -0,0 +1,75 @@
+use crate::dated;
+use crate::GameKey;
+use anyhow::{Error, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+/// Number of session logs kept per game; older ones are deleted.
+const MAX_LOGS: usize = 5;
+
+/// `name` with anything that isn't safe in a file name replaced.
+pub fn safe_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn log_dir(cache_dir: &PathBuf, key: &GameKey) -> PathBuf {
+    cache_dir
+        .join("logs")
+        .join(format!("{:?}-{}", key.launcher, safe_name(&key.id)))
+}
+
+/// Log files for a game, oldest first.
+fn logs(cache_dir: &PathBuf, key: &GameKey) -> Vec<PathBuf> {
+    dated::list(&log_dir(cache_dir, key), |p| {
+        p.extension().map_or(false, |ext| ext == "log")
+    })
+}
+
+pub fn latest(cache_dir: &PathBuf, key: &GameKey) -> Option<PathBuf> {
+    logs(cache_dir, key).pop()
+}
+
+/// Creates the log for a play session, removing the oldest beyond `MAX_LOGS`.
+/// The game writes its output straight into the file, so it keeps working
+/// after the launcher has closed.
+pub fn create(cache_dir: &PathBuf, key: &GameKey, started: u64) -> Result<(PathBuf, File), Error> {
+    let (path, file) = dated::create(&log_dir(cache_dir, key), started, "log", |path| {
+        OpenOptions::new().write(true).create_new(true).open(path)
+    })?;
+    dated::prune(logs(cache_dir, key), MAX_LOGS, |log| fs::remove_file(log));
+    Ok((path, file))
+}
+
+/// Up to the last `count` lines of the log at `path`.
+pub fn tail(path: &PathBuf, count: usize) -> Vec<String> {
+    // Enough for a few lines without reading a long log every frame.
+    const TAIL_BYTES: u64 = 4096;
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return Vec::new(),
+    };
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut contents = Vec::new();
+    if file
+        .seek(SeekFrom::Start(len.saturating_sub(TAIL_BYTES)))
+        .is_err()
+        || file.read_to_end(&mut contents).is_err()
+    {
+        return Vec::new();
+    }
+    let contents = String::from_utf8_lossy(&contents);
+    let lines: Vec<&str> = contents.lines().collect();
+    lines[lines.len().saturating_sub(count)..]
+        .iter()
+        .map(|line| line.trim_end().to_owned())
+        .collect()
+}
@@ -2,45 +2,119 @@
 // Uncomment to turn off console window completely.
 
 use anyhow::{anyhow, Error, Result};
-use clap::{App, Arg};
+use artwork::Artwork;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use config::Config;
+use crop::Crop;
+use details::{draw_details, draw_message, LaunchDetails};
 use dirs;
+use disk::{dir_size, format_size, launcher_totals};
 use epic::{EpicGame, EpicGames, EPIC_GAMES_JSON};
+use epic_manifests::Manifest;
+use glutin::platform::windows::WindowExtWindows;
 use glutin::Icon;
 use glutin_window::GlutinWindow as Window;
 use graphics::{math::Matrix2d, DrawState, Image, Transformed};
+use image_cache::{hash, ImageCache};
 use image_grid::grid::{Color, Grid, TileHandler};
+use images::{Animation, ImageLoader, TileImage};
+use instance::{forward, Instance, IpcCommand};
 use kernel32;
-use opengl_graphics::{GlGraphics, OpenGL, Texture, TextureSettings};
+use launchpad::Launchpad;
+use monitor::{add_running_on_steam, still_running, ChildMonitor, MonitorMsg};
+use opengl_graphics::{GlGraphics, GlyphCache, OpenGL, Texture, TextureSettings};
 use piston::input::keyboard::{Key, ModifierKey};
 use piston::window::{AdvancedWindow, WindowSettings};
-use reqwest;
+use placeholder::draw_placeholder;
+use process::STOP_TIMEOUT;
+use saves::{AutoBackup, SaveManifest};
 use serde::{Deserialize, Serialize};
 use serde_json;
-use std::collections::HashMap;
+use sessions::{
+    format_playtime, format_timestamp, load_running, now, update_running, RunningGame, Session,
+    SessionExit, SessionHistory,
+};
+use sources::{load_refreshed, save_refreshed, Source, SOURCES};
+use stats::{parse_date, Report, StatsFormat};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::{Read, Write};
+use std::io::Write;
 use std::path::PathBuf;
 use std::process::{Child, Command};
 use std::ptr;
 use std::sync::{mpsc, Arc, Mutex};
-use std::thread::{self, sleep};
-use std::time::Duration;
-use steam::{app_info::AppInfo, package_info::PackageInfo, steam_game::SteamGame};
-use twitch::{TwitchDb, TwitchGame};
-use url::Url;
+use std::thread;
+use std::time::Instant;
+use steam::steam_game::SteamGame;
+use store::{StoreAction, StoreCommand};
+use thumbnails::Thumbnails;
+use twitch::TwitchGame;
 use user32;
+use watch::LibraryWatcher;
 use winapi;
 
+mod artwork;
+mod config;
+mod crop;
+mod dated;
+mod details;
+mod disk;
+mod epic_manifests;
+mod image_cache;
+mod images;
+mod instance;
+mod launchpad;
+mod logs;
+mod monitor;
+mod placeholder;
+mod process;
+mod saves;
+mod sessions;
+mod sources;
+mod stats;
+mod store;
+#[cfg(test)]
+mod test_server;
+mod thumbnails;
+mod watch;
+
 const MAX_TILE_WIDTH: usize = 200;
 const MAX_TILE_HEIGHT: usize = 200;
+/// Tiles either side of a drawn one whose images are loaded ahead of scrolling.
+const PREFETCH_TILES: usize = 20;
+/// Textures kept on the GPU before the least recently drawn are dropped.
+const TEXTURE_BUDGET: usize = 300;
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
 enum ImageSource {
     Url(String),
     Path(String),
+    /// Drawn from the title, for games without artwork such as custom ones.
+    None,
+}
+
+impl Default for ImageSource {
+    fn default() -> Self {
+        ImageSource::None
+    }
+}
+
+/// The shapes of artwork stores provide. Tiles look even when they all use the same kind.
+#[derive(Deserialize, Serialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+enum ArtKind {
+    /// Tall box art, like Steam's library_600x900 and Twitch covers.
+    Cover,
+    /// Wide capsule, like Steam's header.
+    Banner,
+    /// Large background image shown behind a game's page.
+    Hero,
+    /// The game's name, usually on a transparent background.
+    Logo,
+    Icon,
 }
 
-#[derive(Deserialize, Serialize, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Deserialize, Serialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
 enum Launcher {
     Steam,
     Twitch,
@@ -48,18 +122,29 @@ enum Launcher {
     Unknown,
 }
 
+/// Identifies a game independent of where it sits in `Doorways::games`.
+#[derive(Deserialize, Serialize, PartialEq, Eq, Hash, Clone, Debug)]
+struct GameKey {
+    launcher: Launcher,
+    id: String,
+}
+
 impl Default for Launcher {
     fn default() -> Self {
         Launcher::Unknown
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone)]
 struct Game {
     id: String,
     title: String,
     image_path: Option<PathBuf>,
+    /// Used when none of the preferred kinds of `artwork` are available.
+    #[serde(default)]
     image_src: ImageSource,
+    #[serde(default)]
+    artwork: HashMap<ArtKind, ImageSource>,
     installed: bool,
     kids: Option<bool>,
     hidden: Option<bool>,
@@ -71,34 +156,36 @@ struct Game {
     args: Option<Vec<String>>,
     #[serde(default)]
     launcher: Launcher,
+    /// Total seconds played, derived from the session history.
+    #[serde(default)]
+    playtime: u64,
+    #[serde(default)]
+    last_played: Option<u64>,
+    /// Bytes on disk, measured from `install_directory`.
+    #[serde(default)]
+    install_size: Option<u64>,
+    /// What the game's store calls it, where that isn't `id`.
+    #[serde(default)]
+    store_id: Option<String>,
 }
 
 impl Game {
-    fn download_img(&self, path: &PathBuf) -> Result<PathBuf, Error> {
-        assert!(path.exists(), "Path for image download does not exist!");
-        let url = match &self.image_src {
-            ImageSource::Url(raw_url) => raw_url,
-            _ => panic!("download_img called without a url"),
-        };
-        let url = Url::parse(&url).expect("Unable to parse url for image");
-        let filename = url
-            .path_segments()
-            .expect("Unable to segments from image url")
-            .last()
-            .expect("Unable to get filename from image url");
-        let image = path.join(filename);
-        if image.exists() {
-            return Ok(image);
-        }
-        let mut resp = reqwest::get(url.as_str()).expect("Unable to retrieve image from url");
-        assert!(resp.status().is_success());
-        let mut buffer = Vec::new();
-        resp.read_to_end(&mut buffer)?;
-        fs::write(&image, buffer)?;
-        Ok(image)
+    fn key(&self) -> GameKey {
+        GameKey {
+            launcher: self.launcher,
+            id: self.id.clone(),
+        }
+    }
+
+    fn launches_directly(&self) -> bool {
+        self.install_directory.is_some() && self.command.is_some()
     }
 
     fn launch(&self) -> Result<Child, Error> {
+        Ok(self.launch_command()?.spawn()?)
+    }
+
+    fn launch_command(&self) -> Result<Command, Error> {
         println!(
             "Launching {:?} {:?} {:?} {:?} {:?}",
             self.install_directory,
@@ -107,7 +194,7 @@ impl Game {
             self.args,
             self.launch_url
         );
-        if self.install_directory.is_some() && self.command.is_some() {
+        if self.launches_directly() {
             let install_directory = PathBuf::from(
                 self.install_directory
                     .as_ref()
@@ -131,15 +218,70 @@ impl Game {
             if self.args.is_some() {
                 launch.args(self.args.as_ref().unwrap());
             }
-            return Ok(launch.spawn()?);
+            process::isolate(&mut launch);
+            return Ok(launch);
         }
         if self.launch_url.is_some() {
-            let mut launch = Command::new("cmd");
-            launch.args(&["/C", "start", self.launch_url.as_ref().unwrap()]);
-            return Ok(launch.spawn()?);
+            return Ok(store::open_url(self.launch_url.as_ref().unwrap()));
         }
         Err(anyhow!("Unable to launch: Missing launch_url or command",))
     }
+
+    /// Every image the game's source gave, whichever kind is preferred.
+    fn all_images(&self) -> Vec<&ImageSource> {
+        let mut images: Vec<&ImageSource> = self.artwork.values().collect();
+        images.push(&self.image_src);
+        images
+    }
+
+    /// Where the game's image can come from, the `preferred` kinds first.
+    fn image_sources(&self, preferred: &[ArtKind]) -> Vec<ImageSource> {
+        let mut sources: Vec<ImageSource> = preferred
+            .iter()
+            .filter_map(|kind| self.artwork.get(kind).cloned())
+            .collect();
+        if self.image_src != ImageSource::None && !sources.contains(&self.image_src) {
+            sources.push(self.image_src.clone());
+        }
+        sources
+    }
+
+    /// How to have the game's store perform `action`. `installed` is only updated
+    /// once a refresh sees the result.
+    fn store_command(&self, action: StoreAction) -> Result<StoreCommand, Error> {
+        let id = match self.launcher {
+            Launcher::Epic => self.epic_id(action)?,
+            _ => self.id.clone(),
+        };
+        store::command(self.launcher, &id, action).ok_or_else(|| {
+            anyhow!(
+                "Unable to {}: not supported for {:?} games",
+                action.name(),
+                self.launcher
+            )
+        })
+    }
+
+    /// Epic ids are display names. Its store wants the catalog id from the
+    /// launcher's manifests, and Legendary the app name in the same manifest.
+    fn epic_id(&self, action: StoreAction) -> Result<String, Error> {
+        let missing = || {
+            anyhow!(
+                "Unable to {}: the Epic launcher has no manifest for {}",
+                action.name(),
+                self.title
+            )
+        };
+        let store_id = self.store_id.as_ref().ok_or_else(missing)?;
+        if action == StoreAction::Install {
+            return Ok(store_id.clone());
+        }
+        epic_manifests::load()
+            .into_iter()
+            .find(|manifest| &manifest.store_id() == store_id)
+            .map(|manifest| manifest.app_name)
+            .ok_or_else(missing)
+    }
 }
 
 fn from_twitch(games: Vec<TwitchGame>) -> Vec<Game> {
@@ -149,6 +291,9 @@ fn from_twitch(games: Vec<TwitchGame>) -> Vec<Game> {
             id: g.asin.to_string(),
             title: g.title.clone(),
             image_src: ImageSource::Url(g.image_url.clone()),
+            artwork: vec![(ArtKind::Cover, ImageSource::Url(g.image_url.clone()))]
+                .into_iter()
+                .collect(),
             installed: g.installed,
             install_directory: g.install_directory.clone(),
             working_subdir_override: g.working_subdir_override.clone(),
@@ -160,6 +305,35 @@ fn from_twitch(games: Vec<TwitchGame>) -> Vec<Game> {
             image_path: None,
             launch_url: g.launch_url.clone(),
             launcher: Launcher::Twitch,
+            playtime: 0,
+            last_played: None,
+            install_size: None,
+            store_id: None,
+        })
+        .collect()
+}
+
+/// Steam keeps each kind of artwork it has downloaded in its library cache, next
+/// to the header it reports as the game's logo.
+fn steam_artwork(id: &str, logo: &str) -> HashMap<ArtKind, ImageSource> {
+    let folder = match PathBuf::from(logo).parent() {
+        Some(folder) => folder.to_path_buf(),
+        None => return HashMap::new(),
+    };
+    let kinds = [
+        (ArtKind::Cover, "library_600x900.jpg"),
+        (ArtKind::Banner, "header.jpg"),
+        (ArtKind::Hero, "library_hero.jpg"),
+        (ArtKind::Logo, "logo.png"),
+    ];
+    kinds
+        .iter()
+        .filter_map(|(kind, name)| {
+            // Older clients prefix files with the app id; newer ones give each app a folder.
+            [folder.join(format!("{}_{}", id, name)), folder.join(name)]
+                .iter()
+                .find(|path| path.is_file())
+                .map(|path| (*kind, ImageSource::Path(path.display().to_string())))
         })
         .collect()
 }
@@ -174,6 +348,7 @@ fn from_steam(games: Vec<SteamGame>) -> Vec<Game> {
             id: g.id.to_string(),
             title: g.title.clone(),
             image_src: ImageSource::Path(g.logo.as_ref().unwrap().clone()),
+            artwork: steam_artwork(&g.id.to_string(), g.logo.as_ref().unwrap()),
             installed: g.installed,
             launch_url: Some(format!("steam://rungameid/{}", g.id)),
             kids: None,
@@ -185,16 +360,19 @@ fn from_steam(games: Vec<SteamGame>) -> Vec<Game> {
             install_directory: None,
             working_subdir_override: None,
             launcher: Launcher::Steam,
+            playtime: 0,
+            last_played: None,
+            install_size: None,
+            store_id: None,
         })
         .collect();
     println!("Steam -- {}", games.len());
     games
 }
 
-fn from_epic(games: Vec<EpicGame>) -> Vec<Game> {
+fn from_epic(games: Vec<EpicGame>, manifests: &[Manifest]) -> Vec<Game> {
     games
         .iter()
-        .filter(|g| g.image_url.is_some())
         .map(|g| {
             let command = &g.launch_command;
             let args = match command {
@@ -204,7 +382,12 @@ fn from_epic(games: Vec<EpicGame>) -> Vec<Game> {
             Game {
                 id: g.display_name.clone(),
                 title: g.display_name.clone(),
-                image_src: ImageSource::Url(g.image_url.as_ref().unwrap().clone()),
+                image_src: match &g.image_url {
+                    Some(url) => ImageSource::Url(url.clone()),
+                    None => ImageSource::None,
+                },
+                // Epic doesn't say which of its key images this is.
+                artwork: HashMap::new(),
                 installed: true,
                 install_directory: Some(g.install_location.clone()),
                 working_subdir_override: None,
@@ -216,6 +399,14 @@ fn from_epic(games: Vec<EpicGame>) -> Vec<Game> {
                 image_path: None,
                 launch_url: None,
                 launcher: Launcher::Epic,
+                playtime: 0,
+                last_played: None,
+                install_size: None,
+                store_id: manifests
+                    .iter()
+                    .find(|m| m.installed_in(&g.install_location))
+                    .or_else(|| manifests.iter().find(|m| m.display_name == g.display_name))
+                    .map(|m| m.store_id()),
             }
         })
         .collect()
@@ -235,6 +426,7 @@ impl VecGame for Vec<Game> {
                     found = true;
                     custom.title = orig.title.clone();
                     custom.image_src = orig.image_src.clone();
+                    custom.artwork = orig.artwork.clone();
                     custom.install_directory = orig.install_directory.clone();
                     custom.working_subdir_override = orig.working_subdir_override.clone();
                     custom.installed = orig.installed.clone();
@@ -242,6 +434,7 @@ impl VecGame for Vec<Game> {
                     custom.args = orig.args.clone();
                     custom.launch_url = orig.launch_url.clone();
                     custom.launcher = orig.launcher.clone();
+                    custom.store_id = orig.store_id.clone();
                 }
             }
             if !found {
@@ -254,6 +447,15 @@ impl VecGame for Vec<Game> {
     }
 }
 
+/// Finds a game by title, preferring an exact match over a case insensitive one.
+fn find_game(games: &Vec<Game>, title: &str) -> Option<usize> {
+    // TODO: Support partial matching
+    games.iter().position(|g| g.title == title).or_else(|| {
+        let title = title.to_lowercase();
+        games.iter().position(|g| g.title.to_lowercase() == title)
+    })
+}
+
 #[derive(Copy, Clone)]
 enum DisplayFilter {
     All,
@@ -262,47 +464,149 @@ enum DisplayFilter {
     NotInterested,
 }
 
+#[derive(Copy, Clone, PartialEq)]
+enum SortOrder {
+    Title,
+    Playtime,
+    LastPlayed,
+    Size,
+}
+
+impl SortOrder {
+    fn from_str(value: &str) -> SortOrder {
+        match value {
+            "playtime" => SortOrder::Playtime,
+            "last-played" => SortOrder::LastPlayed,
+            "size" => SortOrder::Size,
+            _ => SortOrder::Title,
+        }
+    }
+
+    fn next(self) -> SortOrder {
+        match self {
+            SortOrder::Title => SortOrder::Playtime,
+            SortOrder::Playtime => SortOrder::LastPlayed,
+            SortOrder::LastPlayed => SortOrder::Size,
+            SortOrder::Size => SortOrder::Title,
+        }
+    }
+}
+
 enum LaunchStatus {
     Starting,
     Running,
     Success,
     FailedToLaunch(Error),
     Error(i32),
+    Stopped,
+    /// A store action is under way, until a refresh sees its result. Activating
+    /// the tile again sends the action again.
+    Managing(StoreAction),
+    /// A store action was handed off that no refresh will see the end of; says so.
+    Managed(String),
+    /// Not launched because too many games are already running.
+    Refused(String),
 }
 
 struct Doorways {
     games: Vec<Game>,
-    status: Arc<Mutex<HashMap<usize, LaunchStatus>>>,
+    status: Arc<Mutex<HashMap<GameKey, LaunchStatus>>>,
     display_filter: DisplayFilter,
     display_installed: Option<bool>,
     displayed_games: Vec<usize>,
-    images: Vec<Option<Texture>>,
+    sort_order: SortOrder,
+    history: Arc<Mutex<SessionHistory>>,
+    images: Vec<TileImage>,
+    draws: Cell<u64>,
+    /// Where each game sits in `displayed_games`.
+    tile_positions: HashMap<usize, usize>,
     image_folder: PathBuf,
+    image_loader: Option<ImageLoader>,
+    /// Why an image couldn't be loaded, kept until the next attempt.
+    image_errors: RefCell<HashMap<GameKey, String>>,
+    /// Hidden games whose image has since loaded, to be shown again.
+    recovered: RefCell<Vec<usize>>,
+    /// Sizes tiles whose image hasn't loaded; `draw_tile` draws a generated one in its place.
+    placeholder: Option<Texture>,
+    /// Whether tiles are cropped to one shape; starts as configured.
+    uniform_tiles: bool,
+    animation: RefCell<Option<Animation>>,
     edit_mode: bool,
     allow_filter: bool,
     background_color: Option<Color>,
     icons: HashMap<Launcher, Texture>,
-    status_channel: Option<mpsc::Sender<(usize, Launched)>>,
+    launchpad: Option<Launchpad>,
+    monitor_thread: Option<thread::JoinHandle<()>>,
     show_overlay: bool,
+    cache_dir: PathBuf,
+    details: Arc<Mutex<HashMap<GameKey, LaunchDetails>>>,
+    details_for: Option<GameKey>,
+    /// Asked to uninstall once; a second Ctrl+U on the same tile goes ahead.
+    confirm_uninstall: Option<GameKey>,
+    /// Why a tile's last action didn't happen, shown over it until the next key.
+    notice: Option<(GameKey, String)>,
+    glyphs: Option<RefCell<GlyphCache<'static>>>,
+    config: Config,
+    install_sizes: Arc<Mutex<HashMap<GameKey, u64>>>,
+    /// Started with `--watch`; sends what it reloads to `refreshes`.
+    _watcher: Option<LibraryWatcher>,
+    /// Sources reloaded by the watcher or at another invocation's request.
+    refreshes: mpsc::Receiver<(Source, Vec<Game>)>,
+    refresh_sender: mpsc::Sender<(Source, Vec<Game>)>,
+    /// A copy of `games` for commands forwarded by other invocations, which are
+    /// handled off the UI thread.
+    shared_games: Arc<Mutex<Vec<Game>>>,
 }
 
 impl Doorways {
     fn new(cache_dir: PathBuf) -> Doorways {
         let icons = HashMap::new();
+        let (refresh_sender, refreshes) = mpsc::channel();
+        let history = SessionHistory::load(&cache_dir).unwrap_or_else(|err| {
+            eprintln!("Unable to load session history: {}", err);
+            SessionHistory::empty(&cache_dir)
+        });
+        let config = Config::load(&cache_dir).unwrap_or_else(|err| {
+            eprintln!("Unable to load config: {}", err);
+            Config::default()
+        });
         Doorways {
             games: Vec::new(),
             status: Arc::new(Mutex::new(HashMap::new())),
             images: Vec::new(),
+            draws: Cell::new(0),
+            tile_positions: HashMap::new(),
             image_folder: cache_dir.join("images"),
+            image_loader: None,
+            image_errors: RefCell::new(HashMap::new()),
+            recovered: RefCell::new(Vec::new()),
+            placeholder: None,
             display_filter: DisplayFilter::All,
             display_installed: Some(true),
             displayed_games: Vec::new(),
+            sort_order: SortOrder::Title,
+            history: Arc::new(Mutex::new(history)),
             edit_mode: false,
             allow_filter: false,
+            uniform_tiles: config.uniform_tiles,
+            animation: RefCell::new(None),
             background_color: None,
             icons,
-            status_channel: None,
+            launchpad: None,
+            monitor_thread: None,
             show_overlay: true,
+            cache_dir,
+            details: Arc::new(Mutex::new(HashMap::new())),
+            details_for: None,
+            confirm_uninstall: None,
+            notice: None,
+            glyphs: None,
+            config,
+            install_sizes: Arc::new(Mutex::new(HashMap::new())),
+            _watcher: None,
+            refreshes,
+            refresh_sender,
+            shared_games: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
@@ -322,9 +626,70 @@ impl Doorways {
         Ok(())
     }
 
+    fn find_game(&self, title: &str) -> Option<usize> {
+        find_game(&self.games, title)
+    }
+
+    fn apply_history(&mut self) {
+        let totals = self.history.lock().unwrap().totals();
+        for game in self.games.iter_mut() {
+            let total = totals.get(&game.key()).cloned().unwrap_or_default();
+            game.playtime = total.playtime;
+            game.last_played = total.last_played;
+        }
+    }
+
+    /// Indexes of `games` in the current sort order. Games stay sorted by title
+    /// in place so indexes into `images` remain valid.
+    fn ordered(&self, indexes: &mut Vec<usize>) {
+        let games = &self.games;
+        match self.sort_order {
+            SortOrder::Title => indexes.sort_by(|a, b| games[*a].title.cmp(&games[*b].title)),
+            SortOrder::Playtime => {
+                indexes.sort_by(|a, b| games[*b].playtime.cmp(&games[*a].playtime))
+            }
+            SortOrder::LastPlayed => {
+                indexes.sort_by(|a, b| games[*b].last_played.cmp(&games[*a].last_played))
+            }
+            SortOrder::Size => {
+                indexes.sort_by(|a, b| games[*b].install_size.cmp(&games[*a].install_size))
+            }
+        }
+    }
+
+    /// Copies in sizes measured by the background scan so far.
+    fn apply_sizes(&mut self) {
+        let sizes = self.install_sizes.lock().unwrap();
+        for game in self.games.iter_mut() {
+            if let Some(size) = sizes.get(&game.key()) {
+                game.install_size = Some(*size);
+            }
+        }
+    }
+
+    /// Clears installs and uninstalls that a refresh has since seen finish.
+    fn finish_store_actions(&mut self) {
+        let mut statuses = self.status.lock().unwrap();
+        for game in self.games.iter() {
+            let key = game.key();
+            let done = match statuses.get(&key) {
+                Some(LaunchStatus::Managing(StoreAction::Install)) => game.installed,
+                Some(LaunchStatus::Managing(StoreAction::Uninstall)) => !game.installed,
+                _ => false,
+            };
+            if done {
+                statuses.remove(&key);
+            }
+        }
+    }
+
     fn update_filter(&mut self, df: DisplayFilter) {
+        *self.shared_games.lock().unwrap() = self.games.clone();
         self.display_filter = df;
-        self.displayed_games = self
+        self.apply_history();
+        self.apply_sizes();
+        self.finish_store_actions();
+        let mut displayed_games: Vec<usize> = self
             .games
             .iter()
             .enumerate()
@@ -341,48 +706,237 @@ impl Doorways {
             })
             .map(|(i, _g)| i)
             .collect();
+        self.ordered(&mut displayed_games);
+        self.tile_positions = displayed_games
+            .iter()
+            .enumerate()
+            .map(|(position, i)| (*i, position))
+            .collect();
+        self.displayed_games = displayed_games;
+    }
+
+    /// Makes room for the images of games added since the last call. Images are
+    /// only loaded once their tiles are drawn.
+    fn prepare_images(&mut self) {
+        if self.image_loader.is_none() {
+            self.image_loader = Some(ImageLoader::new(
+                &self.image_folder,
+                &self.cache_dir.join("thumbnails"),
+                &self.cache_dir.join("artwork"),
+                &self.config.artwork,
+            ));
+        }
+        let loader = self.image_loader.as_ref().unwrap();
+        let first = self.images.len();
+        for index in first..self.games.len() {
+            let game = &mut self.games[index];
+            game.image_path = loader.source_path(game);
+            self.images.push(TileImage::default());
+        }
+        // Games hidden when their image failed to load are never drawn, so
+        // their images are tried here to find out whether they've been fixed.
+        for index in first..self.games.len() {
+            if self.games[index].hidden == Some(true) {
+                self.request_image(index);
+            }
+        }
+    }
+
+    /// Reloads the images of games whose artwork override was added, changed or
+    /// removed since their image was loaded.
+    fn reload_artwork(&mut self) {
+        let loader = match &self.image_loader {
+            Some(loader) => loader,
+            None => return,
+        };
+        let changed: HashSet<PathBuf> = loader.artwork_changed.try_iter().collect();
+        if changed.is_empty() {
+            return;
+        }
+        let mut reloaded = false;
+        for (game, image) in self.games.iter_mut().zip(self.images.iter_mut()) {
+            let path = loader.source_path(game);
+            let affected = |p: &Option<PathBuf>| p.as_ref().map_or(false, |p| changed.contains(p));
+            if path == game.image_path && !affected(&path) {
+                continue;
+            }
+            game.image_path = path;
+            image.texture.take();
+            image.requested.set(false);
+            self.image_errors.get_mut().remove(&game.key());
+            reloaded = true;
+        }
+        if reloaded {
+            *self.animation.get_mut() = None;
+        }
+    }
+
+    /// Applies whatever changed in the background since the last input. The grid
+    /// only hands out mutable access in `act` and `key_down`, so this runs at the
+    /// end of both, once the tile index they were given has been used.
+    fn apply_changes(&mut self) {
+        self.apply_refreshes();
+        self.reload_artwork();
+        self.show_recovered();
+    }
+
+    /// Shows hidden games again once their image loads.
+    fn show_recovered(&mut self) {
+        let recovered: Vec<usize> = self.recovered.get_mut().drain(..).collect();
+        if recovered.is_empty() {
+            return;
+        }
+        for i in recovered {
+            if let Some(game) = self.games.get_mut(i) {
+                game.hidden = None;
+            }
+        }
+        self.update_filter(self.display_filter);
+    }
+
+    fn request_image(&self, i: usize) {
+        let (loader, image) = match (&self.image_loader, self.images.get(i)) {
+            (Some(loader), Some(image)) => (loader, image),
+            _ => return,
+        };
+        // An artwork override can still give a game without a source image one.
+        let game = &self.games[i];
+        if game.image_path.is_none() && game.image_sources(&self.config.artwork).is_empty() {
+            return;
+        }
+        if !image.requested.replace(true) {
+            loader.request(i, game, self.crop());
+        }
+    }
+
+    /// Loads the image of a tile being drawn, and of the tiles around it.
+    fn touch_image(&self, i: usize) {
+        let draws = self.draws.get() + 1;
+        self.draws.set(draws);
+        if let Some(image) = self.images.get(i) {
+            image.last_drawn.set(draws);
+        }
+        self.request_image(i);
+        if let Some(position) = self.tile_positions.get(&i) {
+            let start = position.saturating_sub(PREFETCH_TILES);
+            let end = (position + PREFETCH_TILES + 1).min(self.displayed_games.len());
+            for nearby in &self.displayed_games[start..end] {
+                self.request_image(*nearby);
+            }
+        }
+    }
+
+    /// What images are cropped to, if tiles are uniform.
+    fn crop(&self) -> Option<Crop> {
+        if !self.uniform_tiles {
+            return None;
+        }
+        Some(Crop {
+            aspect: self.config.tile_aspect,
+            mode: self.config.crop,
+        })
+    }
+
+    /// Switches between cropped and fitted tiles, reloading every image in the new shape.
+    fn toggle_uniform_tiles(&mut self) {
+        self.uniform_tiles = !self.uniform_tiles;
+        *self.animation.get_mut() = None;
+        for image in &mut self.images {
+            image.texture.take();
+            image.requested.set(false);
+        }
+    }
+
+    /// Drops the least recently drawn textures once there are more than the budget allows.
+    fn evict_images(&mut self) {
+        let mut loaded: Vec<(u64, usize)> = self
+            .images
+            .iter()
+            .enumerate()
+            .filter(|(_i, image)| image.texture.get().is_some())
+            .map(|(i, image)| (image.last_drawn.get(), i))
+            .collect();
+        if loaded.len() <= TEXTURE_BUDGET {
+            return;
+        }
+        loaded.sort();
+        let excess = loaded.len() - TEXTURE_BUDGET;
+        for (_last_drawn, i) in loaded.into_iter().take(excess) {
+            let image = &mut self.images[i];
+            image.texture.take();
+            image.requested.set(false);
+        }
     }
 
-    fn load_imgs(&mut self) -> Result<&Doorways, Error> {
-        for (_index, game) in self.games.iter_mut().enumerate() {
-            game.image_path = match &game.image_src {
-                ImageSource::Url(_) => Some(game.download_img(&self.image_folder).unwrap()),
-                ImageSource::Path(path) => Some(PathBuf::from(path)),
+    /// Turns images finished in the background into textures. Has to run on the
+    /// thread that owns the GL context.
+    fn receive_images(&self) {
+        let loader = match &self.image_loader {
+            Some(loader) => loader,
+            None => return,
+        };
+        for loaded in loader.loaded.try_iter() {
+            // `images` is cleared whenever the games are sorted in place.
+            let (image, game) = match (self.images.get(loaded.index), self.games.get(loaded.index))
+            {
+                (Some(image), Some(game)) => (image, game),
+                _ => continue,
             };
-            let contents =
-                std::fs::read(game.image_path.as_ref().unwrap()).expect("Unable to read file");
-            let img = match image::load_from_memory(&contents) {
-                Ok(t) => Ok(t),
-                Err(msg) => {
-                    eprintln!("Unable to load: {}; {}", game.title, msg);
-                    Err(anyhow!(msg))
+            // Requested before the tiles changed shape; it is being loaded again.
+            if loaded.crop != self.crop() {
+                continue;
+            }
+            match loaded.image {
+                Ok(img) => {
+                    let _ = image
+                        .texture
+                        .set(Texture::from_image(&img, &TextureSettings::new()));
+                    self.image_errors.borrow_mut().remove(&game.key());
+                    if game.hidden == Some(true) {
+                        self.recovered.borrow_mut().push(loaded.index);
+                    }
                 }
+                Err(err) => {
+                    eprintln!("Unable to load image for {}: {}", game.title, err);
+                    self.image_errors
+                        .borrow_mut()
+                        .insert(game.key(), err.to_string());
+                }
+            }
+        }
+        for loaded in loader.animated.try_iter() {
+            let mut animation = self.animation.borrow_mut();
+            let animation = match animation.as_mut() {
+                Some(animation) if animation.index == loaded.index => animation,
+                // The selection has moved on since.
+                _ => continue,
             };
-            if img.is_err() {
-                game.hidden = Some(true);
-                self.images.push(None);
+            if loaded.crop != self.crop() {
                 continue;
             }
-            let img = match img.unwrap() {
-                image::DynamicImage::ImageRgba8(img) => img,
-                x => x.to_rgba(),
-            };
-            // Resize to reduce GPU memory consumption
-            let scale = f32::min(
-                MAX_TILE_WIDTH as f32 / img.width() as f32,
-                MAX_TILE_HEIGHT as f32 / img.height() as f32,
-            );
-            let img = image::imageops::resize(
-                &img,
-                (img.width() as f32 * scale) as u32,
-                (img.height() as f32 * scale) as u32,
-                image::imageops::FilterType::Gaussian,
-            );
+            match loaded.frames {
+                Ok(frames) => animation.start(frames),
+                Err(err) => eprintln!(
+                    "Unable to animate image for {}: {}",
+                    self.games[loaded.index].title, err
+                ),
+            }
+        }
+    }
 
-            let texture = Texture::from_image(&img, &TextureSettings::new());
-            self.images.push(Some(texture));
+    /// Loads the frames of the selected tile's image, dropping those of the tile
+    /// selected before.
+    fn animate(&self, i: usize) {
+        let loader = match &self.image_loader {
+            Some(loader) if self.config.animate_covers => loader,
+            _ => return,
+        };
+        let mut animation = self.animation.borrow_mut();
+        if animation.as_ref().map_or(false, |a| a.index == i) {
+            return;
         }
-        Ok(self)
+        *animation = Some(Animation::new(i));
+        loader.request_frames(i, &self.games[i], self.crop());
     }
 
     fn sort(&mut self) {
@@ -390,209 +944,163 @@ impl Doorways {
         self.games
             .sort_unstable_by(|e1, e2| e1.title.cmp(&e2.title));
         self.images.clear();
+        self.recovered.get_mut().clear();
+        *self.animation.get_mut() = None;
     }
 
-    fn icon(&self, i: usize) -> Option<&Texture> {
-        self.icons.get(&self.games[i].launcher)
-    }
-
-    fn start_status_thread(&mut self) {
-        if self.status_channel.is_some() {
-            return ();
+    /// Merges in sources that have been reloaded, and saves them. New games are
+    /// appended, so indexes into `games` and `images` stay valid.
+    fn apply_refreshes(&mut self) {
+        let updates: Vec<(Source, Vec<Game>)> = self.refreshes.try_iter().collect();
+        if updates.is_empty() {
+            return;
         }
-        let (tx, rx) = mpsc::channel::<(usize, Launched)>();
-        self.status_channel = Some(tx);
-        let status = self.status.clone();
-        thread::spawn(move || {
-            ChildMonitor::new(rx, status).process();
+        let mut refreshed = load_refreshed(&self.cache_dir).unwrap_or_else(|err| {
+            eprintln!("Unable to load refresh times: {}", err);
+            HashMap::new()
         });
+        for (source, games) in updates {
+            eprintln!("Refreshed {:?}: {} games", source, games.len());
+            let current = std::mem::replace(&mut self.games, Vec::new());
+            self.games = current.merge_with(games);
+            refreshed.insert(source, now());
+        }
+        self.prepare_images();
+        self.update_filter(self.display_filter);
+        if let Err(err) = self
+            .save(&self.cache_dir)
+            .and_then(|_| save_refreshed(&self.cache_dir, &refreshed))
+        {
+            eprintln!("Unable to save refreshed games: {}", err);
+        }
     }
-}
 
-struct Launched {
-    child: Child,
-    launcher: Launcher,
-    id: String,
-}
-
-struct ChildMonitor {
-    active: HashMap<usize, Launched>,
-    rx: mpsc::Receiver<(usize, Launched)>,
-    status: Arc<Mutex<HashMap<usize, LaunchStatus>>>,
-}
-
-fn steam_status(id: &str) -> Result<LaunchStatus, Error> {
-    use winreg::enums::*;
-    use winreg::RegKey;
-    let key = format!(r"Software\Valve\Steam\Apps\{}", id);
-    // look up regkey
-    let hklm = RegKey::predef(HKEY_CURRENT_USER);
-    let app = hklm.open_subkey(key)?;
-    let running: u32 = app.get_value("Running")?;
-    if running == 0x01 {
-        Ok(LaunchStatus::Running)
-    } else {
-        Ok(LaunchStatus::Success)
-    }
-}
-
-impl ChildMonitor {
-    fn new(
-        rx: mpsc::Receiver<(usize, Launched)>,
-        status: Arc<Mutex<HashMap<usize, LaunchStatus>>>,
-    ) -> ChildMonitor {
-        ChildMonitor {
-            active: HashMap::new(),
-            rx,
-            status,
-        }
-    }
-
-    fn poll_active(&mut self) {
-        let mut to_remove = Vec::<usize>::new();
-        for (i, launched) in self.active.iter_mut() {
-            match launched.child.try_wait() {
-                Ok(Some(exit_status)) => {
-                    let status = if exit_status.success() {
-                        if launched.launcher == Launcher::Steam {
-                            match steam_status(&launched.id) {
-                                Err(msg) => {
-                                    eprintln!("Error getting steam status: {}", msg);
-                                    LaunchStatus::Error(1)
-                                }
-                                Ok(status) => status,
-                            }
-                        } else {
-                            LaunchStatus::Success
-                        }
-                    } else {
-                        LaunchStatus::Error(exit_status.code().expect("Unable to get exit code"))
-                    };
-                    match status {
-                        LaunchStatus::Running => {}
-                        _ => {
-                            to_remove.push(*i);
+    /// Reloads `sources` in the background, for `apply_refreshes` to merge.
+    fn refresher(&self, home: &PathBuf) -> impl Fn(Vec<Source>) + Send + 'static {
+        let updates = self.refresh_sender.clone();
+        let home = home.clone();
+        move |sources| {
+            let updates = updates.clone();
+            let home = home.clone();
+            thread::spawn(move || {
+                for source in sources {
+                    match source.load(&home) {
+                        Ok(games) => {
+                            let _ = updates.send((source, games));
                         }
+                        Err(err) => eprintln!("Unable to refresh {:?}: {}", source, err),
                     }
-                    self.status.lock().unwrap().insert(*i, status);
                 }
-                Ok(None) => {
-                    self.status
-                        .lock()
-                        .unwrap()
-                        .insert(*i, LaunchStatus::Running);
+            });
+        }
+    }
+
+    fn icon(&self, i: usize) -> Option<&Texture> {
+        self.icons.get(&self.games[i].launcher)
+    }
+
+    /// Starts the monitor thread on first use.
+    fn launchpad(&mut self) -> Launchpad {
+        if let Some(launchpad) = &self.launchpad {
+            return launchpad.clone();
+        }
+        let backups = if self.config.backup_saves {
+            match AutoBackup::new(&self.cache_dir, &self.games) {
+                Ok(backups) => Some(backups),
+                Err(err) => {
+                    eprintln!("Unable to load save locations: {}", err);
+                    None
                 }
-                Err(err) => panic!("Error waiting on child: {}", err),
             }
+        } else {
+            None
+        };
+        let mut monitor = ChildMonitor::new(
+            self.status.clone(),
+            self.history.clone(),
+            self.cache_dir.clone(),
+            backups,
+        );
+        let launchpad = Launchpad::new(
+            self.status.clone(),
+            self.details.clone(),
+            monitor.sender(),
+            self.cache_dir.clone(),
+            self.config.limits.clone(),
+        );
+        self.launchpad = Some(launchpad.clone());
+        self.monitor_thread = Some(thread::spawn(move || monitor.process()));
+        launchpad
+    }
+
+    /// Lets the monitor save its state and exit. Running games are left alone.
+    fn stop_status_thread(&mut self) {
+        if let Some(launchpad) = self.launchpad.take() {
+            let _ = launchpad.send(MonitorMsg::Shutdown);
         }
-        for i in to_remove {
-            self.active.remove(&i).expect("Unable to remove.");
+        if let Some(handle) = self.monitor_thread.take() {
+            if handle.join().is_err() {
+                eprintln!("Status thread exited with a panic");
+            }
         }
     }
 
-    fn process(&mut self) {
-        loop {
-            match self.rx.try_recv() {
-                Err(mpsc::TryRecvError::Empty) => {
-                    self.poll_active();
-                    sleep(Duration::from_secs(1));
-                }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    // Should never happen.
-                    panic!("Unexpected disconnection");
+    /// Hands games still running from an earlier launcher (or `--launch`) to the monitor.
+    fn reattach(&mut self) {
+        let running = match load_running(&self.cache_dir) {
+            Ok(running) => running,
+            Err(err) => {
+                eprintln!("Unable to load running games: {}", err);
+                return;
+            }
+        };
+        for game in running {
+            // Games another doorways is still waiting on are shown as running
+            // here, but their session is left to it.
+            let watched = game.monitor_pid.map_or(false, process::is_alive);
+            if !still_running(&game) {
+                if watched {
+                    continue;
                 }
-                Ok((i, child)) => {
-                    self.active.insert(i, child);
+                eprintln!(
+                    "Lost track of {}; its session was not recorded",
+                    game.game_id
+                );
+                let lost = |r: &RunningGame| r.pid == game.pid && r.start == game.start;
+                if let Err(err) =
+                    update_running(&self.cache_dir, |running| running.retain(|r| !lost(r)))
+                {
+                    eprintln!("Unable to save running games: {}", err);
                 }
+                continue;
+            }
+            if let Err(err) = self.launchpad().reattach(game) {
+                eprintln!("Unable to reattach: {}", err);
             }
         }
     }
-}
-
-impl TileHandler for Doorways {
-    fn window_title(&self) -> String {
-        let lock = match self.allow_filter {
-            true => "🔑",
-            false => "🔒",
-        };
-        let install_filter = match self.display_installed {
-            None => "",
-            Some(true) => "[Installed]",
-            Some(false) => "[Not Installed]",
-        };
-        let filter = match &self.display_filter {
-            DisplayFilter::All => "All",
-            DisplayFilter::Dad => "Dad",
-            DisplayFilter::Kids => "Kids",
-            DisplayFilter::NotInterested => "Unknown",
-        };
-        let count = self.displayed_games.len();
-        format!(
-            "Doorways {} (Filter: {}{}{})",
-            count, filter, install_filter, lock
-        )
-    }
 
-    fn tiles(&self) -> &Vec<usize> {
-        &self.displayed_games
+    fn manage(&mut self, i: usize, action: StoreAction) {
+        self.launchpad().manage(&self.games[i], action);
     }
 
-    fn tile(&self, i: usize) -> &Texture {
-        self.images[i].as_ref().unwrap()
-    }
-
-    fn act(&mut self, i: usize) {
-        {
-            let mut status = self.status.lock().unwrap();
-            // Explicitly enumerating to ensure how each case is handled makes sense.
-            // If we are starting or the game is running, don't attempt to launch again.
-            match status.get(&i) {
-                Some(LaunchStatus::Starting) | Some(LaunchStatus::Running) => return,
-                None
-                | Some(LaunchStatus::Error(_))
-                | Some(LaunchStatus::FailedToLaunch(_))
-                | Some(LaunchStatus::Success) => {}
-            };
-            status.insert(i, LaunchStatus::Starting);
-        }
-
-        self.start_status_thread();
-        match &self.status_channel {
-            None => panic!("Unable to start status thread!"),
-            Some(tx) => {
-                let result = self.games[i].launch();
-                match result {
-                    Ok(child) => {
-                        tx.send((
-                            i,
-                            Launched {
-                                child,
-                                launcher: self.games[i].launcher,
-                                id: self.games[i].id.clone(),
-                            },
-                        ))
-                        .unwrap_or_else(|err| panic!("Unable to send to thread: {}", err));
-                        ()
-                    }
-                    Err(err) => {
-                        self.status
-                            .lock()
-                            .unwrap()
-                            .insert(i, LaunchStatus::FailedToLaunch(err));
-                        ()
-                    }
-                };
-            }
-        };
+    fn stop(&mut self, i: usize) {
+        if let Err(err) = self.launchpad().stop(&self.games[i]) {
+            // The console is usually hidden, so say so on the tile.
+            self.notice = Some((self.games[i].key(), format!("Not stopped: {}", err)));
+        }
     }
 
-    fn key_down(
+    fn handle_key(
         &mut self,
         i: usize,
         keycode: Key,
         keymod: ModifierKey,
     ) -> Option<(Key, ModifierKey)> {
         let game_index = self.tiles()[i];
+        // Any other key cancels an uninstall waiting to be confirmed.
+        let confirming = self.confirm_uninstall.take();
+        self.notice = None;
         if self.edit_mode {
             match keycode {
                 Key::K => {
@@ -629,6 +1137,37 @@ impl TileHandler for Doorways {
                 self.allow_filter = !self.allow_filter;
                 return None;
             }
+            if keycode == Key::T {
+                self.toggle_uniform_tiles();
+                return None;
+            }
+            if keycode == Key::X {
+                self.stop(game_index);
+                return None;
+            }
+            // Only from the unlocked launcher, so kids can't uninstall anything.
+            if self.allow_filter && keycode == Key::U {
+                let key = self.games[game_index].key();
+                if confirming.as_ref() == Some(&key) {
+                    self.manage(game_index, StoreAction::Uninstall);
+                } else {
+                    self.confirm_uninstall = Some(key);
+                }
+                return None;
+            }
+            if self.allow_filter && keycode == Key::V {
+                self.manage(game_index, StoreAction::Verify);
+                return None;
+            }
+            // Not Ctrl+I, as I already toggles the installed filter.
+            if keycode == Key::L {
+                let key = self.games[game_index].key();
+                self.details_for = match self.details_for.take() {
+                    Some(shown) if shown == key => None,
+                    _ => Some(key),
+                };
+                return None;
+            }
         };
 
         if !self.allow_filter {
@@ -659,12 +1198,85 @@ impl TileHandler for Doorways {
                 };
                 self.update_filter(self.display_filter);
             }
+            Key::S => {
+                self.sort_order = self.sort_order.next();
+                self.update_filter(self.display_filter);
+            }
             _ => return Some((keycode, keymod)),
         }
         None
     }
+}
+
+impl TileHandler for Doorways {
+    fn window_title(&self) -> String {
+        let lock = match self.allow_filter {
+            true => "🔑",
+            false => "🔒",
+        };
+        let install_filter = match self.display_installed {
+            None => "",
+            Some(true) => "[Installed]",
+            Some(false) => "[Not Installed]",
+        };
+        let filter = match &self.display_filter {
+            DisplayFilter::All => "All",
+            DisplayFilter::Dad => "Dad",
+            DisplayFilter::Kids => "Kids",
+            DisplayFilter::NotInterested => "Unknown",
+        };
+        let sort = match self.sort_order {
+            SortOrder::Title => "",
+            SortOrder::Playtime => " [Most Played]",
+            SortOrder::LastPlayed => " [Recently Played]",
+            SortOrder::Size => " [Largest]",
+        };
+        let count = self.displayed_games.len();
+        format!(
+            "Doorways {} (Filter: {}{}{}){}",
+            count, filter, install_filter, lock, sort
+        )
+    }
+
+    fn tiles(&self) -> &Vec<usize> {
+        &self.displayed_games
+    }
+
+    fn tile(&self, i: usize) -> &Texture {
+        self.receive_images();
+        self.touch_image(i);
+        match self.images.get(i).and_then(|image| image.texture.get()) {
+            Some(texture) => texture,
+            None => self
+                .placeholder
+                .as_ref()
+                .expect("Placeholder texture is created with the window"),
+        }
+    }
+
+    fn act(&mut self, i: usize) {
+        self.launchpad().launch(&self.games, i);
+        self.evict_images();
+        self.apply_changes();
+    }
+
+    fn key_down(
+        &mut self,
+        i: usize,
+        keycode: Key,
+        keymod: ModifierKey,
+    ) -> Option<(Key, ModifierKey)> {
+        let unhandled = self.handle_key(i, keycode, keymod);
+        // Scrolling is done with keys, so this is when tiles go out of view.
+        self.evict_images();
+        self.apply_changes();
+        unhandled
+    }
 
     fn highlight_color(&self, i: usize) -> Color {
+        // Only asked of the selected tile, and only once the grid has moved the
+        // selection, so this follows arrow keys and re-sorts alike.
+        self.animate(i);
         if let Some(kids) = self.games[i].kids {
             if kids {
                 return [0.0, 1.0, 0.0, 1.0];
@@ -689,25 +1301,107 @@ impl TileHandler for Doorways {
         target_height: usize,
     ) {
         let image = self.tile(i);
-        let (scale, width, height) = self.compute_size(&image, target_width, target_height);
+        let animation = self.animation.borrow();
+        let image = animation
+            .as_ref()
+            .filter(|a| a.index == i)
+            .and_then(|a| a.frame())
+            .unwrap_or(image);
+        let loaded = self
+            .images
+            .get(i)
+            .map_or(false, |image| image.texture.get().is_some());
+        let (scale, mut width, mut height) = self.compute_size(&image, target_width, target_height);
+        // Placeholders take the shape loaded images will be cropped to.
+        if let (false, Some(crop)) = (loaded, self.crop()) {
+            let (w, h) = crop.fit(target_width, target_height);
+            width = w;
+            height = h;
+        }
         let x_image_margin = (target_width - width) / 2;
         let y_image_margin = (target_height - height) / 2;
 
         let state = DrawState::default();
-        Image::new().draw(
-            image,
-            &state,
-            transform
-                .trans(x_image_margin as f64, y_image_margin as f64)
-                .zoom(scale.into()),
-            gl,
-        );
+        match &self.glyphs {
+            // Still loading, failed to load or there's no artwork at all.
+            Some(glyphs) if !loaded => draw_placeholder(
+                &self.games[i].title,
+                self.games[i].launcher,
+                &mut glyphs.borrow_mut(),
+                transform.trans(x_image_margin as f64, y_image_margin as f64),
+                gl,
+                width,
+                height,
+            ),
+            _ => Image::new().draw(
+                image,
+                &state,
+                transform
+                    .trans(x_image_margin as f64, y_image_margin as f64)
+                    .zoom(scale.into()),
+                gl,
+            ),
+        }
+        let key = self.games[i].key();
+        let message = match &self.notice {
+            Some((notice_for, notice)) if *notice_for == key => Some(notice.as_str()),
+            _ if self.confirm_uninstall.as_ref() == Some(&key) => {
+                Some("Press Ctrl+U again to uninstall")
+            }
+            _ => None,
+        };
+        if let Some(message) = message {
+            if let Some(glyphs) = &self.glyphs {
+                draw_message(
+                    message,
+                    &mut glyphs.borrow_mut(),
+                    transform,
+                    gl,
+                    target_width,
+                    target_height,
+                );
+                return ();
+            }
+        }
+        if self.details_for.as_ref() == Some(&key) {
+            if let Some(glyphs) = &self.glyphs {
+                let statuses = self.status.lock().unwrap();
+                let details = self.details.lock().unwrap();
+                draw_details(
+                    details.get(&key),
+                    statuses.get(&key),
+                    &mut glyphs.borrow_mut(),
+                    transform,
+                    gl,
+                    target_width,
+                    target_height,
+                );
+                return ();
+            }
+        }
         let (color, gray_out) = {
             let mut statuses = self.status.lock().unwrap();
-            let status = statuses.get_mut(&i);
+            let status = statuses.get_mut(&key);
             if status.is_none() {
                 return ();
             }
+            let message = match &status {
+                Some(LaunchStatus::Refused(reason)) => Some(reason.as_str()),
+                Some(LaunchStatus::Managing(action)) => Some(action.progress()),
+                Some(LaunchStatus::Managed(message)) => Some(message.as_str()),
+                _ => None,
+            };
+            if let (Some(message), Some(glyphs)) = (message, &self.glyphs) {
+                draw_message(
+                    message,
+                    &mut glyphs.borrow_mut(),
+                    transform,
+                    gl,
+                    target_width,
+                    target_height,
+                );
+                return ();
+            }
             let mut gray_out = false;
             let color = match status.unwrap() {
                 LaunchStatus::Starting => {
@@ -718,6 +1412,13 @@ impl TileHandler for Doorways {
                 LaunchStatus::Success => [1.0, 0.0, 1.0, 1.0],
                 LaunchStatus::Error(_) => [1.0, 0.0, 0.0, 1.0],
                 LaunchStatus::FailedToLaunch(_) => [0.8, 0.8, 0.8, 1.0],
+                LaunchStatus::Stopped => [1.0, 0.6, 0.0, 1.0],
+                LaunchStatus::Managing(_) => {
+                    gray_out = true;
+                    [0.0, 0.0, 0.0, 0.6]
+                }
+                LaunchStatus::Managed(_) => [0.0, 0.6, 1.0, 1.0],
+                LaunchStatus::Refused(_) => [1.0, 1.0, 0.0, 1.0],
             };
             (color, gray_out)
         };
@@ -772,6 +1473,23 @@ fn hide_console_window() {
     }
 }
 
+fn focus_window(hwnd: isize) {
+    unsafe {
+        if user32::IsIconic(hwnd as _) != 0 {
+            user32::ShowWindow(hwnd as _, winapi::um::winuser::SW_RESTORE);
+        }
+        user32::SetForegroundWindow(hwnd as _);
+    }
+}
+
+/// The sources `--refresh` names, or all of them.
+fn refresh_sources(matches: &ArgMatches) -> Result<Vec<Source>, Error> {
+    match matches.values_of("refresh") {
+        Some(values) if values.len() > 0 => values.map(Source::from_str).collect(),
+        _ => Ok(SOURCES.to_vec()),
+    }
+}
+
 fn main() -> Result<()> {
     let matches = App::new("doorways")
         .about("A unified launcher for common game libraries.")
@@ -792,10 +1510,27 @@ fn main() -> Result<()> {
                 .long("list")
                 .help("List the known games."),
         )
+        .arg(
+            Arg::with_name("sort")
+                .long("sort")
+                .takes_value(true)
+                .possible_values(&["title", "playtime", "last-played", "size"])
+                .default_value("title")
+                .help("Order games by title, total playtime, when they were last played or install size."),
+        )
         .arg(
             Arg::with_name("refresh")
                 .long("refresh")
-                .help("Refresh the list of games from source."),
+                .takes_value(true)
+                .min_values(0)
+                .use_delimiter(true)
+                .possible_values(&["steam", "twitch", "epic"])
+                .help("Refresh the list of games from all sources, or just the ones given."),
+        )
+        .arg(
+            Arg::with_name("watch")
+                .long("watch")
+                .help("Refresh the launcher's games when a store's library changes."),
         )
         .arg(
             Arg::with_name("launch")
@@ -804,6 +1539,160 @@ fn main() -> Result<()> {
                 .takes_value(true)
                 .help("Launch the specified game."),
         )
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about("Summarize play sessions per game, launcher and week.")
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .takes_value(true)
+                        .help("Only include sessions on or after this date (YYYY-MM-DD)."),
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .takes_value(true)
+                        .help("Only include sessions on or before this date (YYYY-MM-DD)."),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["text", "csv", "json"])
+                        .default_value("text")
+                        .help("Output format."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("logs")
+                .about("Print the output captured from the latest session of a game.")
+                .arg(
+                    Arg::with_name("game")
+                        .required(true)
+                        .help("Title of the game."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("stop")
+                .about("Stop a running game and everything it started.")
+                .arg(
+                    Arg::with_name("game")
+                        .required(true)
+                        .help("Title of the game to stop."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("du")
+                .about("Show the largest installs and games that haven't been played in a while.")
+                .arg(
+                    Arg::with_name("top")
+                        .long("top")
+                        .takes_value(true)
+                        .default_value("20")
+                        .help("How many of the largest installs to list."),
+                )
+                .arg(
+                    Arg::with_name("months")
+                        .long("months")
+                        .takes_value(true)
+                        .default_value("6")
+                        .help("List installed games not played in this many months."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("backup")
+                .about("Back up save games listed in saves.json.")
+                .arg(
+                    Arg::with_name("game")
+                        .help("Title of the game. Backs up every listed game if left out."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("restore")
+                .about("Restore a game's saves from a backup.")
+                .arg(
+                    Arg::with_name("game")
+                        .required(true)
+                        .help("Title of the game."),
+                )
+                .arg(
+                    Arg::with_name("version")
+                        .long("version")
+                        .takes_value(true)
+                        .help("Backup to restore, as shown by --list. Defaults to the latest."),
+                )
+                .arg(
+                    Arg::with_name("list")
+                        .long("list")
+                        .help("List the game's backups instead of restoring."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("art")
+                .about("Manage local artwork overrides.")
+                .subcommand(
+                    SubCommand::with_name("set")
+                        .about("Use an image instead of the one the game's store provides.")
+                        .arg(
+                            Arg::with_name("game")
+                                .required(true)
+                                .help("Title of the game."),
+                        )
+                        .arg(
+                            Arg::with_name("image")
+                                .required(true)
+                                .help("Image file or URL to copy into the artwork folder."),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("cache")
+                .about("Report how much space cached images use, or clean them up.")
+                .subcommand(
+                    SubCommand::with_name("prune")
+                        .about("Remove images and thumbnails no game uses any more."),
+                )
+                .subcommand(
+                    SubCommand::with_name("verify")
+                        .about("Check that every cached image decodes."),
+                )
+                .subcommand(
+                    SubCommand::with_name("repair")
+                        .about("Download missing, empty or broken cached images again."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("install")
+                .about("Install a game through its store.")
+                .arg(
+                    Arg::with_name("game")
+                        .required(true)
+                        .help("Title of the game."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("uninstall")
+                .about("Uninstall a game through its store.")
+                .arg(
+                    Arg::with_name("game")
+                        .required(true)
+                        .help("Title of the game."),
+                )
+                .arg(
+                    Arg::with_name("yes")
+                        .long("yes")
+                        .help("Uninstall without asking first."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("Have the store check a game's files and repair them.")
+                .arg(
+                    Arg::with_name("game")
+                        .required(true)
+                        .help("Title of the game."),
+                ),
+        )
         .get_matches();
 
     if matches.is_present("launcher") {
@@ -811,30 +1700,84 @@ fn main() -> Result<()> {
     }
     let home = dirs::home_dir().unwrap();
     let doorways_cache = home.join(".doorways");
+    fs::create_dir_all(&doorways_cache)?;
+    // Only one launcher window may own games.json; later invocations hand off to it.
+    let mut instance_lock = None;
+    if matches.is_present("launcher") {
+        match Instance::acquire(&doorways_cache)? {
+            Instance::Secondary(peer) => {
+                if matches.is_present("refresh") {
+                    forward(&peer, IpcCommand::Refresh(refresh_sources(&matches)?))?;
+                }
+                // Windows only lets the process the user just started hand on the
+                // right to come to the front; the launcher can't take it itself.
+                unsafe { winapi::um::winuser::AllowSetForegroundWindow(peer.pid) };
+                forward(&peer, IpcCommand::Focus)?;
+                return Ok(());
+            }
+            Instance::Primary(lock) => instance_lock = Some(lock),
+        }
+    } else if let Some(title) = matches.value_of("launch") {
+        if let Some(peer) = instance::running(&doorways_cache) {
+            forward(&peer, IpcCommand::Launch(title.to_owned()))?;
+            return Ok(());
+        }
+    }
     let mut doorways = if !doorways_cache.join("games.json").exists() {
         Doorways::new(doorways_cache.clone())
     } else {
         Doorways::load(doorways_cache.clone())?
     };
+    doorways.sort_order = SortOrder::from_str(matches.value_of("sort").unwrap());
     if matches.is_present("refresh") {
+        // Only the launcher holding the lock may write games.json.
+        if instance_lock.is_none() {
+            instance::ensure_not_running(&doorways_cache, "refreshing")?;
+        }
         eprintln!("Creating initial games list.");
+        let sources = refresh_sources(&matches)?;
+        let mut refreshed = load_refreshed(&doorways_cache).unwrap_or_else(|err| {
+            eprintln!("Unable to load refresh times: {}", err);
+            HashMap::new()
+        });
         // Reset hidden status during refresh
         for game in doorways.games.iter_mut() {
-            game.hidden = None;
-        }
-        let app_infos = AppInfo::load()?;
-        let pkg_infos = PackageInfo::load()?;
-        let steam = from_steam(SteamGame::from(&app_infos, &pkg_infos)?);
-        eprintln!("Steam games: {}", steam.len());
-        doorways.games = doorways.games.merge_with(steam);
-        let twitch_cache = home.join(".twitch");
-        let twitch_db = TwitchDb::load(&twitch_cache)?;
-        let twitch = from_twitch(TwitchGame::from_db(&twitch_db)?);
-        eprintln!("Twitch games: {}", twitch.len());
-        doorways.games = doorways.games.merge_with(twitch);
-        let epic_games = EpicGame::load(&home.join(".epic"))?;
-        let epic = from_epic(epic_games);
-        doorways.games = doorways.games.merge_with(epic);
+            if sources.iter().any(|s| s.launcher() == game.launcher) {
+                game.hidden = None;
+            }
+        }
+        // A failing source is reported and skipped so the others still update.
+        for source in sources {
+            let started = Instant::now();
+            match source.load(&home) {
+                Ok(games) => {
+                    eprintln!(
+                        "{:?} games: {} ({:.1}s)",
+                        source,
+                        games.len(),
+                        started.elapsed().as_secs_f32()
+                    );
+                    doorways.games = doorways.games.merge_with(games);
+                    refreshed.insert(source, now());
+                }
+                Err(err) => {
+                    let last = match refreshed.get(&source) {
+                        Some(time) => format_timestamp(*time),
+                        None => "never".to_owned(),
+                    };
+                    eprintln!(
+                        "Unable to refresh {:?} after {:.1}s: {} (last refreshed: {})",
+                        source,
+                        started.elapsed().as_secs_f32(),
+                        err,
+                        last
+                    );
+                }
+            }
+        }
+        // Saved first, so refresh times are only recorded for games that were kept.
+        doorways.save(&doorways_cache)?;
+        save_refreshed(&doorways_cache, &refreshed)?;
     };
 
     if matches.is_present("launcher") {
@@ -856,10 +1799,55 @@ fn main() -> Result<()> {
             .set_window_icon(Some(Icon::from_bytes(doorways_bytes)?));
         window.ctx.window().set_maximized(true);
         let mut gl = GlGraphics::new(opengl);
-        // TODO: Add support for downloading of images without loading into textures
-        doorways.load_imgs()?;
+        doorways.prepare_images();
+        doorways.reattach();
+        disk::scan(&doorways.games, doorways.install_sizes.clone());
+        if matches.is_present("watch") {
+            match LibraryWatcher::start(&home, doorways.refresh_sender.clone()) {
+                Ok(watcher) => doorways._watcher = Some(watcher),
+                Err(err) => eprintln!("Unable to watch the library: {}", err),
+            }
+        }
         doorways.update_filter(DisplayFilter::Kids);
+        let hwnd = window.ctx.window().hwnd() as isize;
+        if let Some(lock) = instance_lock.as_mut() {
+            let launchpad = doorways.launchpad();
+            let games = doorways.shared_games.clone();
+            let refresh = doorways.refresher(&home);
+            lock.listen(move |command| match command {
+                IpcCommand::Focus => focus_window(hwnd),
+                IpcCommand::Launch(title) => {
+                    let games = games.lock().unwrap();
+                    match find_game(&games, &title) {
+                        Some(i) => launchpad.launch(&games, i),
+                        None => eprintln!("Unable to find game {}", title),
+                    }
+                }
+                IpcCommand::Refresh(sources) => refresh(sources),
+                IpcCommand::Stop(title) => {
+                    let games = games.lock().unwrap();
+                    let stopped = find_game(&games, &title)
+                        .ok_or_else(|| anyhow!("Unable to find game {}", title))
+                        .and_then(|i| launchpad.stop(&games[i]));
+                    if let Err(err) = stopped {
+                        eprintln!("Unable to stop {}: {}", title, err);
+                    }
+                }
+            });
+        }
         let settings = TextureSettings::new().filter(texture::Filter::Linear);
+        doorways.placeholder = Some(Texture::from_image(
+            &image::RgbaImage::from_pixel(
+                MAX_TILE_WIDTH as u32,
+                MAX_TILE_HEIGHT as u32,
+                image::Rgba([40, 40, 40, 255]),
+            ),
+            &settings,
+        ));
+        doorways.glyphs = Some(RefCell::new(
+            GlyphCache::from_bytes(include_bytes!("../DejaVuSans.ttf"), (), settings)
+                .map_err(|err| anyhow!("Unable to load font: {:?}", err))?,
+        ));
         doorways.icons.insert(
             Launcher::Steam,
             Texture::from_image(
@@ -901,31 +1889,494 @@ fn main() -> Result<()> {
         let mut grid = Grid::new(Box::new(&mut doorways), MAX_TILE_WIDTH, MAX_TILE_HEIGHT);
         grid.allow_draw_tile = false;
         grid.run(&mut window, &mut gl)?;
+        doorways.stop_status_thread();
+        doorways.apply_sizes();
         eprintln!("Game count before save: {}", doorways.games.len());
         doorways.save(&doorways_cache)?;
         return Ok(());
     }
 
+    if let Some(stats) = matches.subcommand_matches("stats") {
+        let from = match stats.value_of("from") {
+            Some(date) => Some(parse_date(date, false)?),
+            None => None,
+        };
+        let to = match stats.value_of("to") {
+            Some(date) => Some(parse_date(date, true)?),
+            None => None,
+        };
+        let format = StatsFormat::from_str(stats.value_of("format").unwrap());
+        let history = doorways.history.lock().unwrap();
+        let report = Report::build(&doorways.games, history.sessions(), from, to);
+        print!("{}", report.render(format)?);
+        return Ok(());
+    }
+
     if matches.is_present("list") {
         let installed_only = matches.value_of("installed").unwrap().parse::<bool>()?;
-        for game in doorways.games {
-            if installed_only && !game.installed {
-                continue;
+        let mut listed: Vec<usize> = (0..doorways.games.len())
+            .filter(|i| !installed_only || doorways.games[*i].installed)
+            .collect();
+        doorways.apply_history();
+        doorways.ordered(&mut listed);
+        for i in listed {
+            let game = &doorways.games[i];
+            let last_played = match game.last_played {
+                Some(time) => format_timestamp(time),
+                None => "never".to_owned(),
+            };
+            let size = match game.install_size {
+                Some(size) => format_size(size),
+                None => "-".to_owned(),
+            };
+            println!(
+                "{:<50} {:>10} {:<16} {:>10}",
+                game.title,
+                format_playtime(game.playtime),
+                last_played,
+                size
+            );
+        }
+        return Ok(());
+    }
+
+    if let Some(du) = matches.subcommand_matches("du") {
+        let top = du.value_of("top").unwrap().parse::<usize>()?;
+        let months = du.value_of("months").unwrap().parse::<u64>()?;
+        eprintln!("Measuring installed games...");
+        for game in doorways.games.iter_mut() {
+            if let Some(size) = disk::install_size(game) {
+                game.install_size = Some(size);
             }
-            println!("{}", game.title);
+        }
+        // The launcher saves games.json when it closes and would overwrite the sizes;
+        // it measures them itself anyway.
+        if instance::running(&doorways_cache).is_some() {
+            eprintln!("Not saving sizes while the launcher is running");
+        } else {
+            doorways.save(&doorways_cache)?;
+        }
+        doorways.apply_history();
+
+        let mut installed: Vec<&Game> = doorways.games.iter().filter(|g| g.installed).collect();
+        installed.sort_by(|a, b| b.install_size.cmp(&a.install_size));
+        let size = |game: &Game| match game.install_size {
+            Some(size) => format_size(size),
+            None => "unknown".to_owned(),
+        };
+        println!("Largest installs:");
+        for game in installed
+            .iter()
+            .filter(|g| g.install_size.is_some())
+            .take(top)
+        {
+            println!(
+                "  {:<50} {:>10} {:?}",
+                game.title,
+                format_size(game.install_size.unwrap()),
+                game.launcher
+            );
+        }
+        println!("Per launcher:");
+        for (launcher, size) in launcher_totals(&doorways.games) {
+            println!(
+                "  {:<50} {:>10}",
+                format!("{:?}", launcher),
+                format_size(size)
+            );
+        }
+        let unmeasured = installed
+            .iter()
+            .filter(|g| g.install_size.is_none())
+            .count();
+        if unmeasured > 0 {
+            println!(
+                "  {} installed games whose install folder couldn't be found aren't counted",
+                unmeasured
+            );
+        }
+        // Months are approximated as 30 days.
+        let cutoff = now().saturating_sub(months * 30 * 24 * 60 * 60);
+        println!("Not played in {} months:", months);
+        for game in installed
+            .iter()
+            .filter(|g| g.last_played.map_or(true, |t| t < cutoff))
+        {
+            let last_played = match game.last_played {
+                Some(time) => format_timestamp(time),
+                None => "never".to_owned(),
+            };
+            println!("  {:<50} {:>10} {}", game.title, size(game), last_played);
         }
         return Ok(());
     }
 
-    if let Some(game_to_launch) = matches.value_of("launch") {
-        for game in doorways.games {
-            // TODO: Support partial and case insensitive matching
-            if game.title == game_to_launch {
-                game.launch()?;
+    if let Some(backup) = matches.subcommand_matches("backup") {
+        let manifest = SaveManifest::load(&doorways_cache)?;
+        let games: Vec<&Game> = match backup.value_of("game") {
+            Some(title) => {
+                let i = doorways
+                    .find_game(title)
+                    .ok_or_else(|| anyhow!("Unable to find game {}", title))?;
+                vec![&doorways.games[i]]
+            }
+            None => doorways.games.iter().collect(),
+        };
+        let single = games.len() == 1;
+        for game in games {
+            let entry = match manifest.find(&game.key(), &game.title) {
+                Some(entry) => entry,
+                None if single => {
+                    return Err(anyhow!(
+                        "No save locations known for {}; add them to saves.json",
+                        game.title
+                    ))
+                }
+                None => continue,
+            };
+            // Keep going so one game's problem doesn't stop the rest being backed up.
+            match saves::backup(&doorways_cache, &game.title, entry) {
+                Ok(Some((dir, count))) => {
+                    println!("{}: {} files in {}", game.title, count, dir.display())
+                }
+                Ok(None) => println!("{}: no save files found", game.title),
+                Err(err) => eprintln!("Unable to back up {}: {}", game.title, err),
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(restore) = matches.subcommand_matches("restore") {
+        let title = restore.value_of("game").unwrap();
+        let i = doorways
+            .find_game(title)
+            .ok_or_else(|| anyhow!("Unable to find game {}", title))?;
+        let game = &doorways.games[i];
+        let versions = saves::versions(&doorways_cache, &game.title);
+        if restore.is_present("list") {
+            for version in versions {
+                let name = version.file_name().unwrap().to_string_lossy().to_string();
+                let created = name
+                    .parse::<u64>()
+                    .map(format_timestamp)
+                    .unwrap_or_default();
+                println!("{} {}", name, created);
+            }
+            return Ok(());
+        }
+        let version = match restore.value_of("version") {
+            Some(name) => versions
+                .into_iter()
+                .find(|v| v.file_name().map_or(false, |n| n == name))
+                .ok_or_else(|| anyhow!("No backup {} for {}", name, game.title))?,
+            None => versions
+                .into_iter()
+                .last()
+                .ok_or_else(|| anyhow!("No backups for {}", game.title))?,
+        };
+        let manifest = SaveManifest::load(&doorways_cache)?;
+        let entry = manifest.find(&game.key(), &game.title);
+        let count = saves::restore(&doorways_cache, &game.title, entry, &version)?;
+        println!("Restored {} files for {}", count, game.title);
+        return Ok(());
+    }
+
+    if let Some(set) = matches
+        .subcommand_matches("art")
+        .and_then(|art| art.subcommand_matches("set"))
+    {
+        let title = set.value_of("game").unwrap();
+        let i = doorways
+            .find_game(title)
+            .ok_or_else(|| anyhow!("Unable to find game {}", title))?;
+        let game = &doorways.games[i];
+        let artwork = Artwork::new(&doorways_cache.join("artwork"));
+        let cache = ImageCache::load(&doorways_cache.join("images"));
+        // Read first, as the new image may be written over it.
+        let preferred = Config::load(&doorways_cache)?.artwork;
+        let previous =
+            images::source_path(&cache, &artwork, &preferred, game).and_then(|p| fs::read(p).ok());
+        let path = artwork.set(game, set.value_of("image").unwrap())?;
+        // Thumbnails are keyed by their source, so the old one would never be read again.
+        if let Some(previous) = previous {
+            Thumbnails::new(&doorways_cache.join("thumbnails")).remove(&previous);
+        }
+        println!("Artwork for {} is now {}", game.title, path.display());
+        return Ok(());
+    }
+
+    if let Some(cache) = matches.subcommand_matches("cache") {
+        let image_folder = doorways_cache.join("images");
+        let thumbnail_folder = doorways_cache.join("thumbnails");
+        let artwork_folder = doorways_cache.join("artwork");
+        let images = ImageCache::load(&image_folder);
+        match cache.subcommand_name() {
+            Some("prune") => {
+                instance::ensure_not_running(&doorways_cache, "pruning the cache")?;
+                // Without a games list every image would look unused.
+                if doorways.games.is_empty() {
+                    return Err(anyhow!(
+                        "No games are known, so nothing was pruned; run `doorways --refresh` first"
+                    ));
+                }
+                let urls: HashSet<String> = doorways
+                    .games
+                    .iter()
+                    .flat_map(|g| g.all_images())
+                    .filter_map(|source| match source {
+                        ImageSource::Url(url) => Some(url.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                let (count, size) = images.prune(&urls)?;
+                println!("Removed {} images ({})", count, format_size(size));
+
+                // Thumbnails are named after the image they were made from, so keep
+                // those of every image a game could still show.
+                let mut sources: Vec<PathBuf> = images
+                    .entries()
+                    .into_iter()
+                    .map(|(_url, path)| path)
+                    .collect();
+                sources.extend(
+                    doorways
+                        .games
+                        .iter()
+                        .flat_map(|g| g.all_images())
+                        .filter_map(|source| match source {
+                            ImageSource::Path(path) => Some(PathBuf::from(path)),
+                            _ => None,
+                        }),
+                );
+                sources.extend(Artwork::new(&artwork_folder).files());
+                let hashes: HashSet<String> = sources
+                    .iter()
+                    .filter_map(|path| fs::read(path).ok())
+                    .map(|contents| hash(&contents))
+                    .collect();
+                let (count, size) = Thumbnails::new(&thumbnail_folder).prune(&hashes)?;
+                println!("Removed {} thumbnails ({})", count, format_size(size));
+            }
+            Some("verify") => {
+                let broken = images.broken();
+                for (url, problem) in &broken {
+                    println!("{}: {}", url, problem);
+                }
+                let total = images.entries().len();
+                if !broken.is_empty() {
+                    return Err(anyhow!(
+                        "{} of {} cached images are broken; run `doorways cache repair`",
+                        broken.len(),
+                        total
+                    ));
+                }
+                println!("All {} cached images decode", total);
+            }
+            Some("repair") => {
+                instance::ensure_not_running(&doorways_cache, "repairing the cache")?;
+                let client = reqwest::Client::new();
+                let broken = images.broken();
+                for (url, problem) in &broken {
+                    images.forget(url);
+                    match images.fetch(&client, url) {
+                        Ok(_) => println!("Downloaded {} again ({})", url, problem),
+                        Err(err) => eprintln!("Unable to download {}: {}", url, err),
+                    }
+                }
+                // A server can keep sending something that isn't an image.
+                let still_broken = images.broken();
+                for (url, problem) in &still_broken {
+                    eprintln!("Still broken: {}: {}", url, problem);
+                }
+                println!(
+                    "Repaired {} of {} broken images",
+                    broken.len().saturating_sub(still_broken.len()),
+                    broken.len()
+                );
+            }
+            _ => {
+                let folders = [
+                    ("Images", &image_folder),
+                    ("Thumbnails", &thumbnail_folder),
+                    ("Artwork", &artwork_folder),
+                ];
+                let mut total = 0;
+                for (name, folder) in folders.iter() {
+                    let size = dir_size(folder);
+                    total += size;
+                    println!(
+                        "{:<12}{:>10}  {}",
+                        name,
+                        format_size(size),
+                        folder.display()
+                    );
+                }
+                println!("{:<12}{:>10}", "Total", format_size(total));
+                println!("{} downloaded images", images.entries().len());
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(show_logs) = matches.subcommand_matches("logs") {
+        let title = show_logs.value_of("game").unwrap();
+        let i = doorways
+            .find_game(title)
+            .ok_or_else(|| anyhow!("Unable to find game {}", title))?;
+        let game = &doorways.games[i];
+        let log = logs::latest(&doorways_cache, &game.key())
+            .ok_or_else(|| anyhow!("No logs for {}", game.title))?;
+        eprintln!("{}", log.display());
+        print!("{}", String::from_utf8_lossy(&fs::read(log)?));
+        return Ok(());
+    }
+
+    for action in &[
+        StoreAction::Install,
+        StoreAction::Uninstall,
+        StoreAction::Verify,
+    ] {
+        if let Some(manage) = matches.subcommand_matches(action.name()) {
+            let title = manage.value_of("game").unwrap();
+            let i = doorways
+                .find_game(title)
+                .ok_or_else(|| anyhow!("Unable to find game {}", title))?;
+            let game = &doorways.games[i];
+            let StoreCommand { mut command, waits } = game.store_command(*action)?;
+            if *action == StoreAction::Uninstall && !manage.is_present("yes") {
+                eprint!("Uninstall {}? [y/N] ", game.title);
+                let mut answer = String::new();
+                std::io::stdin().read_line(&mut answer)?;
+                if !answer.trim().eq_ignore_ascii_case("y") {
+                    return Err(anyhow!("Not uninstalling {}", game.title));
+                }
+            }
+            let mut child = command.spawn()?;
+            if waits {
+                let status = child.wait()?;
+                if !status.success() {
+                    return Err(anyhow!(
+                        "Unable to {} {}: {}",
+                        action.name(),
+                        game.title,
+                        status
+                    ));
+                }
+                eprintln!("{}: {}", action.done(), game.title);
                 return Ok(());
             }
+            eprintln!(
+                "Asked {:?} to {} {}",
+                game.launcher,
+                action.name(),
+                game.title
+            );
+            return Ok(());
+        }
+    }
+
+    if let Some(stop) = matches.subcommand_matches("stop") {
+        let title = stop.value_of("game").unwrap();
+        let i = doorways
+            .find_game(title)
+            .ok_or_else(|| anyhow!("Unable to find game {}", title))?;
+        let game = &doorways.games[i];
+        // What was recorded is the process that handed the game to its store.
+        if !game.launches_directly() {
+            return Err(anyhow!(
+                "{} was started through {:?}, so it can't be stopped from here; quit it in the game",
+                game.title,
+                game.launcher
+            ));
+        }
+        let entry = load_running(&doorways_cache)?
+            .into_iter()
+            .find(|r| r.key() == game.key())
+            .ok_or_else(|| anyhow!("{} is not running", game.title))?;
+        let is_entry = |r: &RunningGame| r.pid == entry.pid && r.start == entry.start;
+        // The game may have exited unseen and its process id been reused since.
+        if !process::is_same(entry.pid, entry.start) {
+            update_running(&doorways_cache, |running| running.retain(|r| !is_entry(r)))?;
+            return Err(anyhow!("{} is not running", game.title));
+        }
+        // The launcher watching it stops it itself, so its session is recorded as stopped.
+        if let Some(peer) =
+            instance::running(&doorways_cache).filter(|peer| entry.monitor_pid == Some(peer.pid))
+        {
+            forward(&peer, IpcCommand::Stop(game.title.clone()))?;
+            eprintln!("Asked the launcher to stop {}", game.title);
+            return Ok(());
+        }
+        process::terminate(entry.pid, STOP_TIMEOUT)?;
+        // A live `--launch` records the session itself once it sees the exit.
+        if !entry.monitor_pid.map_or(false, process::is_alive) {
+            update_running(&doorways_cache, |running| running.retain(|r| !is_entry(r)))?;
+            doorways.history.lock().unwrap().record(Session {
+                game_id: entry.game_id,
+                launcher: entry.launcher,
+                start: entry.start,
+                end: now(),
+                exit: SessionExit::Stopped,
+                log: None,
+            })?;
+        }
+        eprintln!("Stopped {}", game.title);
+        return Ok(());
+    }
+
+    if let Some(game_to_launch) = matches.value_of("launch") {
+        match doorways.find_game(game_to_launch) {
+            Some(i) => {
+                let game = &doorways.games[i];
+                if !game.installed {
+                    game.store_command(StoreAction::Install)?.command.spawn()?;
+                    eprintln!("Installing {}", game.title);
+                    return Ok(());
+                }
+                // Games started by another `--launch` are counted through running.json,
+                // and Steam games however they were started.
+                let mut running: Vec<&Game> = load_running(&doorways_cache)?
+                    .iter()
+                    .filter(|r| still_running(r))
+                    .filter_map(|r| doorways.games.iter().find(|g| g.key() == r.key()))
+                    .collect();
+                add_running_on_steam(&doorways.games, &mut running);
+                if let Some(reason) = doorways.config.limits.check(game, &running) {
+                    return Err(anyhow!("Not launching {}: {}", game.title, reason));
+                }
+                let mut child = game.launch()?;
+                // Anything else only handed the game to its store.
+                if !game.launches_directly() {
+                    return Ok(());
+                }
+                let entry = RunningGame {
+                    game_id: game.id.clone(),
+                    launcher: game.launcher,
+                    pid: child.id(),
+                    start: now(),
+                    monitor_pid: Some(std::process::id()),
+                };
+                update_running(&doorways_cache, |running| running.push(entry.clone()))?;
+                // Wait for the game, so its entry goes once it exits and its session is recorded.
+                let exit = match child.wait() {
+                    Ok(status) if status.success() => SessionExit::Success,
+                    Ok(status) => SessionExit::Error(status.code().unwrap_or(-1)),
+                    Err(_) => SessionExit::Unknown,
+                };
+                update_running(&doorways_cache, |running| {
+                    running.retain(|r| !(r.pid == entry.pid && r.start == entry.start))
+                })?;
+                doorways.history.lock().unwrap().record(Session {
+                    game_id: entry.game_id,
+                    launcher: entry.launcher,
+                    start: entry.start,
+                    end: now(),
+                    exit,
+                    log: None,
+                })?;
+            }
+            None => eprintln!("Unable to find game {}", game_to_launch),
         }
-        eprintln!("Unable to find game {}", game_to_launch);
         return Ok(());
     }
 
@@ -0,0 +1,408 @@
+use crate::process::{self, STOP_TIMEOUT};
+use crate::saves::AutoBackup;
+use crate::sessions::{now, update_running, RunningGame, Session, SessionExit, SessionHistory};
+use crate::{Game, GameKey, LaunchStatus, Launcher};
+use anyhow::{anyhow, Error, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Child;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, sleep};
+use std::time::{Duration, Instant};
+
+/// How often Steam's registry entry, or a reattached process, is checked.
+const POLL: Duration = Duration::from_secs(1);
+/// How long Steam gets to start a game, which includes updating it first.
+const STEAM_START_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+pub struct Launched {
+    child: Child,
+    key: GameKey,
+    started: u64,
+    log: Option<PathBuf>,
+    handed_off: bool,
+}
+
+impl Launched {
+    pub fn new(child: Child, game: &Game, started: u64, log: Option<PathBuf>) -> Launched {
+        Launched {
+            child,
+            key: game.key(),
+            started,
+            log,
+            // Steam is asked whether its games are running; other stores can't be.
+            handed_off: !game.launches_directly() && game.launcher != Launcher::Steam,
+        }
+    }
+}
+
+pub enum MonitorMsg {
+    Launch(Launched),
+    /// Picks a game back up that was started before this instance of doorways.
+    Reattach(RunningGame),
+    Stop(GameKey),
+    /// Sent once the graceful stop timeout has passed for the given pid.
+    Kill(GameKey, u32),
+    /// Sent by a waiter thread when its game is done.
+    Exited(GameKey, LaunchStatus),
+    Shutdown,
+}
+
+/// A game the monitor is waiting on. The child itself is owned by its waiter thread.
+struct Tracked {
+    started: u64,
+    pid: u32,
+    stopping: bool,
+    /// Reattached games aren't our children, so their exit code is unknown.
+    reattached: bool,
+    log: Option<PathBuf>,
+    /// Only the store client was started, so when the game ends is unknown.
+    handed_off: bool,
+    /// The doorways that launched the game with `--launch` and is still waiting
+    /// on it. It records the session; here the game is only shown as running.
+    watched_by: Option<u32>,
+}
+
+pub struct ChildMonitor {
+    active: HashMap<GameKey, Tracked>,
+    tx: mpsc::Sender<MonitorMsg>,
+    rx: mpsc::Receiver<MonitorMsg>,
+    status: Arc<Mutex<HashMap<GameKey, LaunchStatus>>>,
+    history: Arc<Mutex<SessionHistory>>,
+    cache_dir: PathBuf,
+    backups: Option<AutoBackup>,
+}
+
+fn steam_status(id: &str) -> Result<LaunchStatus, Error> {
+    use winreg::enums::*;
+    use winreg::RegKey;
+    let key = format!(r"Software\Valve\Steam\Apps\{}", id);
+    // look up regkey
+    let hklm = RegKey::predef(HKEY_CURRENT_USER);
+    let app = hklm.open_subkey(key)?;
+    let running: u32 = app.get_value("Running")?;
+    if running == 0x01 {
+        Ok(LaunchStatus::Running)
+    } else {
+        Ok(LaunchStatus::Success)
+    }
+}
+
+/// Follows a Steam game through the registry until it is no longer running.
+fn wait_for_steam(id: &str) -> LaunchStatus {
+    // The launch command exits as soon as Steam has the URL, before the game
+    // starts, so wait for it to be running before waiting for it to stop.
+    let asked = Instant::now();
+    loop {
+        match steam_status(id) {
+            Ok(LaunchStatus::Running) => break,
+            Ok(_) if asked.elapsed() < STEAM_START_TIMEOUT => sleep(POLL),
+            Ok(_) => {
+                return LaunchStatus::FailedToLaunch(anyhow!(
+                    "Steam did not start it within {:?}",
+                    STEAM_START_TIMEOUT
+                ))
+            }
+            Err(msg) => {
+                eprintln!("Error getting steam status: {}", msg);
+                return LaunchStatus::Error(1);
+            }
+        }
+    }
+    loop {
+        match steam_status(id) {
+            Ok(LaunchStatus::Running) => sleep(POLL),
+            Ok(status) => return status,
+            Err(msg) => {
+                eprintln!("Error getting steam status: {}", msg);
+                return LaunchStatus::Error(1);
+            }
+        }
+    }
+}
+
+/// Blocks until the game is done and works out how it ended.
+fn wait_for(mut child: Child, key: &GameKey) -> LaunchStatus {
+    let exit_status = match child.wait() {
+        Ok(exit_status) => exit_status,
+        Err(err) => {
+            return LaunchStatus::FailedToLaunch(anyhow!("Error waiting on child: {}", err))
+        }
+    };
+    if !exit_status.success() {
+        // No exit code means the process was ended by a signal.
+        return LaunchStatus::Error(exit_status.code().unwrap_or(-1));
+    }
+    if key.launcher != Launcher::Steam {
+        return LaunchStatus::Success;
+    }
+    // Steam launches hand off to the client immediately.
+    wait_for_steam(&key.id)
+}
+
+/// Waits on a process that isn't our child, so it can only be polled.
+fn wait_for_pid(pid: u32, start: u64, key: &GameKey) -> LaunchStatus {
+    if key.launcher == Launcher::Steam {
+        return wait_for_steam(&key.id);
+    }
+    while process::is_same(pid, start) {
+        sleep(POLL);
+    }
+    LaunchStatus::Success
+}
+
+/// Whether a game left behind by an earlier doorways is still being played.
+/// Its process id may have been reused since, so the process must also have
+/// been started when the game was.
+pub fn still_running(running: &RunningGame) -> bool {
+    if running.launcher == Launcher::Steam {
+        return match steam_status(&running.game_id) {
+            Ok(LaunchStatus::Running) => true,
+            _ => false,
+        };
+    }
+    process::is_same(running.pid, running.start)
+}
+
+/// Adds the Steam games Steam says are running to `running`, however they
+/// were started. Games started by other stores' clients can't be seen.
+pub fn add_running_on_steam<'a>(games: &'a [Game], running: &mut Vec<&'a Game>) {
+    for game in games {
+        if game.launcher != Launcher::Steam || !game.installed {
+            continue;
+        }
+        if running.iter().any(|r| r.key() == game.key()) {
+            continue;
+        }
+        if let Ok(LaunchStatus::Running) = steam_status(&game.id) {
+            running.push(game);
+        }
+    }
+}
+
+impl ChildMonitor {
+    pub fn new(
+        status: Arc<Mutex<HashMap<GameKey, LaunchStatus>>>,
+        history: Arc<Mutex<SessionHistory>>,
+        cache_dir: PathBuf,
+        backups: Option<AutoBackup>,
+    ) -> ChildMonitor {
+        let (tx, rx) = mpsc::channel::<MonitorMsg>();
+        ChildMonitor {
+            active: HashMap::new(),
+            tx,
+            rx,
+            status,
+            history,
+            cache_dir,
+            backups,
+        }
+    }
+
+    pub fn sender(&self) -> mpsc::Sender<MonitorMsg> {
+        self.tx.clone()
+    }
+
+    fn set_status(&self, key: &GameKey, status: LaunchStatus) {
+        self.status.lock().unwrap().insert(key.clone(), status);
+    }
+
+    /// Replaces our entries in running.json with the games being tracked now.
+    fn save_running(&self) {
+        let own = std::process::id();
+        let ours: Vec<(&GameKey, &Tracked)> = self
+            .active
+            .iter()
+            .filter(|(_key, tracked)| tracked.watched_by.is_none())
+            .collect();
+        let saved = update_running(&self.cache_dir, |running| {
+            // Keep games another doorways is watching, and those nobody is watching
+            // that are still running, for the next launcher to reattach to.
+            running.retain(|r| {
+                r.monitor_pid != Some(own)
+                    && !ours.iter().any(|(key, _tracked)| **key == r.key())
+                    && (r.monitor_pid.map_or(false, process::is_alive) || still_running(r))
+            });
+            running.extend(ours.iter().map(|(key, tracked)| RunningGame {
+                game_id: key.id.clone(),
+                launcher: key.launcher,
+                pid: tracked.pid,
+                start: tracked.started,
+                monitor_pid: Some(own),
+            }));
+        });
+        if let Err(err) = saved {
+            eprintln!("Unable to save running games: {}", err);
+        }
+    }
+
+    fn record_session(&self, key: &GameKey, tracked: &Tracked, status: &LaunchStatus) {
+        let exit = match status {
+            _ if tracked.handed_off => SessionExit::Unknown,
+            LaunchStatus::Success if tracked.reattached => SessionExit::Unknown,
+            LaunchStatus::Success => SessionExit::Success,
+            LaunchStatus::Error(code) => SessionExit::Error(*code),
+            LaunchStatus::Stopped => SessionExit::Stopped,
+            _ => SessionExit::Unknown,
+        };
+        let session = Session {
+            game_id: key.id.clone(),
+            launcher: key.launcher,
+            start: tracked.started,
+            // Counting the time until the store client exited would be made up playtime.
+            end: if tracked.handed_off {
+                tracked.started
+            } else {
+                now()
+            },
+            exit,
+            log: tracked.log.clone(),
+        };
+        if let Err(err) = self.history.lock().unwrap().record(session) {
+            eprintln!("Unable to record session for {}: {}", key.id, err);
+        }
+    }
+
+    fn track(&mut self, key: &GameKey, tracked: Tracked) {
+        self.active.insert(key.clone(), tracked);
+        self.set_status(key, LaunchStatus::Running);
+        self.save_running();
+    }
+
+    fn launch(&mut self, launched: Launched) {
+        let Launched {
+            child,
+            key,
+            started,
+            log,
+            handed_off,
+        } = launched;
+        self.track(
+            &key,
+            Tracked {
+                started,
+                pid: child.id(),
+                stopping: false,
+                reattached: false,
+                log,
+                handed_off,
+                watched_by: None,
+            },
+        );
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let status = wait_for(child, &key);
+            // The monitor is gone if the launcher has exited; nothing left to report to.
+            let _ = tx.send(MonitorMsg::Exited(key, status));
+        });
+    }
+
+    fn reattach(&mut self, running: RunningGame) {
+        let key = running.key();
+        if self.active.contains_key(&key) {
+            return;
+        }
+        let pid = running.pid;
+        let start = running.start;
+        let own = std::process::id();
+        let watched_by = running
+            .monitor_pid
+            .filter(|monitor| *monitor != own && process::is_alive(*monitor));
+        self.track(
+            &key,
+            Tracked {
+                started: running.start,
+                pid,
+                stopping: false,
+                reattached: true,
+                log: None,
+                handed_off: false,
+                watched_by,
+            },
+        );
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let status = wait_for_pid(pid, start, &key);
+            let _ = tx.send(MonitorMsg::Exited(key, status));
+        });
+    }
+
+    fn stop(&mut self, key: GameKey) {
+        let tracked = match self.active.get_mut(&key) {
+            Some(tracked) => tracked,
+            None => return,
+        };
+        if let Err(err) = process::signal(tracked.pid, false) {
+            eprintln!("Unable to stop {}: {}", key.id, err);
+            return;
+        }
+        tracked.stopping = true;
+        let pid = tracked.pid;
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            sleep(STOP_TIMEOUT);
+            let _ = tx.send(MonitorMsg::Kill(key, pid));
+        });
+    }
+
+    fn kill(&mut self, key: GameKey, pid: u32) {
+        // The game may have exited and been relaunched while waiting.
+        if let Some(tracked) = self.active.get(&key).filter(|t| t.pid == pid && t.stopping) {
+            eprintln!(
+                "{} did not exit after {:?}, killing it",
+                key.id, STOP_TIMEOUT
+            );
+            if let Err(err) = process::signal(tracked.pid, true) {
+                eprintln!("Unable to kill {}: {}", key.id, err);
+            }
+        }
+    }
+
+    fn exited(&mut self, key: GameKey, status: LaunchStatus) {
+        let tracked = match self.active.remove(&key) {
+            Some(tracked) => tracked,
+            None => return,
+        };
+        if tracked.watched_by.is_some() {
+            // Its session and exit status are the other doorways' to record.
+            self.status.lock().unwrap().remove(&key);
+            self.save_running();
+            return;
+        }
+        let status = if tracked.stopping {
+            LaunchStatus::Stopped
+        } else {
+            status
+        };
+        // A game that never started wasn't played.
+        let started = !matches!(status, LaunchStatus::FailedToLaunch(_));
+        if started {
+            self.record_session(&key, &tracked, &status);
+        }
+        self.set_status(&key, status);
+        self.save_running();
+        // A handed off game may not even have started yet, so there's nothing new to back up.
+        if let (true, false, Some(backups)) = (started, tracked.handed_off, &self.backups) {
+            backups.after_session(&key);
+        }
+    }
+
+    /// Handles launches and exits until the launcher shuts down.
+    pub fn process(&mut self) {
+        // The monitor holds a sender for its waiter threads, so `recv` only
+        // fails if it has been dropped some other way.
+        while let Ok(msg) = self.rx.recv() {
+            match msg {
+                MonitorMsg::Launch(launched) => self.launch(launched),
+                MonitorMsg::Reattach(running) => self.reattach(running),
+                MonitorMsg::Stop(key) => self.stop(key),
+                MonitorMsg::Kill(key, pid) => self.kill(key, pid),
+                MonitorMsg::Exited(key, status) => self.exited(key, status),
+                MonitorMsg::Shutdown => break,
+            }
+        }
+        // Games keep running after the launcher closes; leave them in running.json
+        // so the next launcher can reattach to them.
+        self.save_running();
+    }
+}
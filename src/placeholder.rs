@@ -0,0 +1,79 @@
+use crate::details::wrap;
+use crate::Launcher;
+use graphics::character::CharacterCache;
+use graphics::{math::Matrix2d, DrawState, Text, Transformed};
+use image_grid::grid::Color;
+use opengl_graphics::{GlGraphics, GlyphCache};
+
+const FONT_SIZE: u32 = 18;
+const LINE_HEIGHT: f64 = 22.0;
+const MARGIN: f64 = 10.0;
+
+fn launcher_color(launcher: Launcher) -> Color {
+    match launcher {
+        Launcher::Steam => [0.09, 0.16, 0.25, 1.0],
+        Launcher::Twitch => [0.39, 0.25, 0.65, 1.0],
+        Launcher::Epic => [0.2, 0.2, 0.2, 1.0],
+        Launcher::Unknown => [0.25, 0.3, 0.35, 1.0],
+    }
+}
+
+/// Breaks `text` between words where it can, and within words that don't fit on a line.
+fn wrap_words(glyphs: &mut GlyphCache, text: &str, width: f64) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() {
+            word.to_owned()
+        } else {
+            format!("{} {}", line, word)
+        };
+        if glyphs.width(FONT_SIZE, &candidate).unwrap_or(0.0) <= width {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+        let mut pieces = wrap(glyphs, FONT_SIZE, word, width);
+        line = pieces.pop().unwrap_or_default();
+        lines.extend(pieces);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Stands in for a game without a usable image: its title on the launcher's color.
+pub fn draw_placeholder(
+    title: &str,
+    launcher: Launcher,
+    glyphs: &mut GlyphCache,
+    transform: Matrix2d,
+    gl: &mut GlGraphics,
+    width: usize,
+    height: usize,
+) {
+    let (width, height) = (width as f64, height as f64);
+    graphics::rectangle(
+        launcher_color(launcher),
+        [0.0, 0.0, width, height],
+        transform,
+        gl,
+    );
+
+    let lines = wrap_words(glyphs, title, width - 2.0 * MARGIN);
+    let fits = ((height - 2.0 * MARGIN) / LINE_HEIGHT).max(1.0) as usize;
+    let shown = lines.len().min(fits);
+    // Center the block of lines vertically and each line horizontally.
+    let mut y = (height - shown as f64 * LINE_HEIGHT) / 2.0 + FONT_SIZE as f64;
+    let state = DrawState::default();
+    let text = Text::new_color([1.0, 1.0, 1.0, 1.0], FONT_SIZE);
+    for line in lines.iter().take(shown) {
+        let line_width = glyphs.width(FONT_SIZE, line).unwrap_or(0.0);
+        let x = ((width - line_width) / 2.0).max(MARGIN);
+        let _ = text.draw(line, glyphs, &state, transform.trans(x, y), gl);
+        y += LINE_HEIGHT;
+    }
+}
@@ -0,0 +1,182 @@
+use anyhow::{anyhow, Error, Result};
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// How long a game gets to exit on its own before it is killed.
+pub const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+/// Seconds a process may have been created before or after its recorded start.
+const START_TOLERANCE: u64 = 10;
+
+/// Starts the child in its own process group so that stopping it also stops
+/// anything it spawned (launchers, anti-cheat helpers, ...).
+pub fn isolate(command: &mut Command) {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(CREATE_NEW_PROCESS_GROUP);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+}
+
+fn run_quietly(command: &mut Command) -> Result<bool, Error> {
+    Ok(command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?
+        .success())
+}
+
+#[cfg(windows)]
+pub fn is_alive(pid: u32) -> bool {
+    let output = Command::new("tasklist")
+        .args(&["/FI", &format!("PID eq {}", pid), "/NH", "/FO", "CSV"])
+        .output();
+    match output {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).contains(&format!("\"{}\"", pid)),
+        Err(_) => false,
+    }
+}
+
+#[cfg(unix)]
+pub fn is_alive(pid: u32) -> bool {
+    run_quietly(Command::new("kill").args(&["-0", &pid.to_string()])).unwrap_or(false)
+}
+
+/// When the process was created, in seconds since the epoch, if it is running.
+#[cfg(windows)]
+pub fn started_at(pid: u32) -> Option<u64> {
+    // A FILETIME: 100ns intervals since 1601.
+    #[repr(C)]
+    #[derive(Default)]
+    struct FileTime {
+        low: u32,
+        high: u32,
+    }
+    const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+    const STILL_ACTIVE: u32 = 259;
+    const SECONDS_BEFORE_EPOCH: u64 = 11_644_473_600;
+    let mut created = FileTime::default();
+    let mut unused = [
+        FileTime::default(),
+        FileTime::default(),
+        FileTime::default(),
+    ];
+    let mut exit_code = 0u32;
+    let running = unsafe {
+        let handle = kernel32::OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            return None;
+        }
+        // An exited process can still be opened while something holds a handle to it.
+        let running = kernel32::GetExitCodeProcess(handle, &mut exit_code) != 0
+            && exit_code == STILL_ACTIVE
+            && kernel32::GetProcessTimes(
+                handle,
+                &mut created as *mut FileTime as *mut _,
+                &mut unused[0] as *mut FileTime as *mut _,
+                &mut unused[1] as *mut FileTime as *mut _,
+                &mut unused[2] as *mut FileTime as *mut _,
+            ) != 0;
+        kernel32::CloseHandle(handle);
+        running
+    };
+    if !running {
+        return None;
+    }
+    let intervals = (created.high as u64) << 32 | created.low as u64;
+    (intervals / 10_000_000).checked_sub(SECONDS_BEFORE_EPOCH)
+}
+
+#[cfg(unix)]
+pub fn started_at(pid: u32) -> Option<u64> {
+    let output = Command::new("ps")
+        .args(&["-o", "etimes=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    let elapsed: u64 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .ok()?;
+    crate::sessions::now().checked_sub(elapsed)
+}
+
+/// Whether `pid` is still the process started at `start`, in seconds since the
+/// epoch, rather than a later one that was given the same id.
+pub fn is_same(pid: u32, start: u64) -> bool {
+    match started_at(pid) {
+        Some(created) => created.max(start) - created.min(start) <= START_TOLERANCE,
+        None => false,
+    }
+}
+
+#[cfg(windows)]
+pub fn signal(pid: u32, force: bool) -> Result<bool, Error> {
+    let pid = pid.to_string();
+    let mut args = vec!["/PID", pid.as_str(), "/T"];
+    if force {
+        args.push("/F");
+    }
+    run_quietly(Command::new("taskkill").args(&args))
+}
+
+#[cfg(unix)]
+pub fn signal(pid: u32, force: bool) -> Result<bool, Error> {
+    let sig = if force { "-KILL" } else { "-TERM" };
+    // Signal the whole group first, falling back to the lone process when it
+    // isn't a group leader (e.g. it was not started by us).
+    let group = format!("-{}", pid);
+    if run_quietly(Command::new("kill").args(&[sig, "--", &group]))? {
+        return Ok(true);
+    }
+    run_quietly(Command::new("kill").args(&[sig, &pid.to_string()]))
+}
+
+fn wait_for_exit<F: FnMut() -> bool>(alive: &mut F, timeout: Duration) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if !alive() {
+            return true;
+        }
+        sleep(Duration::from_millis(250));
+    }
+    !alive()
+}
+
+/// Asks the process (and its children) to exit, killing them if they are
+/// still around after `timeout`.
+pub fn terminate(pid: u32, timeout: Duration) -> Result<(), Error> {
+    terminate_with(pid, timeout, || is_alive(pid))
+}
+
+/// Same as `terminate`, with the caller deciding whether the process is still
+/// alive. Parents should use `Child::try_wait` so zombies aren't seen as alive.
+pub fn terminate_with<F: FnMut() -> bool>(
+    pid: u32,
+    timeout: Duration,
+    mut alive: F,
+) -> Result<(), Error> {
+    if !alive() {
+        return Err(anyhow!("Process {} is not running", pid));
+    }
+    signal(pid, false)?;
+    if wait_for_exit(&mut alive, timeout) {
+        return Ok(());
+    }
+    eprintln!(
+        "Process {} did not exit after {:?}, killing it",
+        pid, timeout
+    );
+    signal(pid, true)?;
+    if wait_for_exit(&mut alive, Duration::from_secs(2)) {
+        Ok(())
+    } else {
+        Err(anyhow!("Unable to kill process {}", pid))
+    }
+}
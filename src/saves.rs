@@ -0,0 +1,270 @@
+use crate::dated;
+use crate::logs::safe_name;
+use crate::sessions::now;
+use crate::{Game, GameKey, Launcher};
+use anyhow::{anyhow, Error, Result};
+use dirs;
+use glob::glob;
+use serde::{Deserialize, Serialize};
+use serde_json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+
+const MANIFEST_JSON: &str = "saves.json";
+const INDEX_JSON: &str = "backup.json";
+/// Number of backups kept per game; older ones are deleted.
+const MAX_BACKUPS: usize = 10;
+
+/// Where one game keeps its saves.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct SaveEntry {
+    /// Matches Steam games by app id.
+    #[serde(default)]
+    pub steam_id: Option<String>,
+    /// Matches any game by title, ignoring case.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Files, folders or glob patterns. May start with `<home>` or `<winAppData>`
+    /// and contain `<steamuserid>`.
+    pub paths: Vec<String>,
+}
+
+/// Save locations edited by hand in `saves.json` next to `games.json`.
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct SaveManifest {
+    pub games: Vec<SaveEntry>,
+}
+
+impl SaveManifest {
+    pub fn load(cache_dir: &PathBuf) -> Result<SaveManifest, Error> {
+        let path = cache_dir.join(MANIFEST_JSON);
+        if !path.exists() {
+            return Ok(SaveManifest::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn find(&self, key: &GameKey, title: &str) -> Option<&SaveEntry> {
+        let by_id = |e: &&SaveEntry| {
+            key.launcher == Launcher::Steam && e.steam_id.as_ref() == Some(&key.id)
+        };
+        let title = title.to_lowercase();
+        let by_title = |e: &&SaveEntry| {
+            e.title
+                .as_ref()
+                .map_or(false, |t| t.to_lowercase() == title)
+        };
+        self.games
+            .iter()
+            .find(by_id)
+            .or_else(|| self.games.iter().find(by_title))
+    }
+}
+
+/// The Steam account id that names the user's folder under `userdata`.
+fn steam_user_id() -> Option<String> {
+    use winreg::enums::*;
+    use winreg::RegKey;
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let process = hkcu
+        .open_subkey(r"Software\Valve\Steam\ActiveProcess")
+        .ok()?;
+    let user: u32 = process.get_value("ActiveUser").ok()?;
+    if user == 0 {
+        return None;
+    }
+    Some(user.to_string())
+}
+
+fn placeholder(name: &str) -> Option<String> {
+    match name {
+        "<home>" => dirs::home_dir().map(|p| p.display().to_string()),
+        // Roaming AppData on Windows.
+        "<winAppData>" => dirs::data_dir().map(|p| p.display().to_string()),
+        "<steamuserid>" => steam_user_id(),
+        _ => None,
+    }
+}
+
+fn expand(path: &str) -> Result<String, Error> {
+    let mut path = path.to_owned();
+    for name in &["<home>", "<winAppData>", "<steamuserid>"] {
+        if path.contains(name) {
+            let value = placeholder(name).ok_or_else(|| anyhow!("Unable to work out {}", name))?;
+            path = path.replace(name, &value);
+        }
+    }
+    Ok(path)
+}
+
+/// Every file under `path`, or `path` itself if it is a file.
+fn files_under(path: &PathBuf) -> Vec<PathBuf> {
+    if path.is_file() {
+        return vec![path.clone()];
+    }
+    match fs::read_dir(path) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .flat_map(|e| files_under(&e.path()))
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct BackupFile {
+    original: PathBuf,
+    /// Relative to the backup folder.
+    stored: PathBuf,
+}
+
+/// Written into each backup so it can be restored to where it came from.
+#[derive(Deserialize, Serialize)]
+struct BackupIndex {
+    title: String,
+    created: u64,
+    files: Vec<BackupFile>,
+}
+
+fn backups_dir(cache_dir: &PathBuf, title: &str) -> PathBuf {
+    cache_dir.join("backups").join(safe_name(title))
+}
+
+/// Backups of a game, oldest first.
+pub fn versions(cache_dir: &PathBuf, title: &str) -> Vec<PathBuf> {
+    dated::list(&backups_dir(cache_dir, title), |p| {
+        p.join(INDEX_JSON).exists()
+    })
+}
+
+fn snapshot(
+    cache_dir: &PathBuf,
+    title: &str,
+    entry: &SaveEntry,
+) -> Result<Option<(PathBuf, usize)>, Error> {
+    let mut files = Vec::new();
+    let mut roots = 0;
+    for pattern in &entry.paths {
+        let pattern = expand(pattern)?;
+        for found in glob(&pattern)?.filter_map(|p| p.ok()) {
+            // Each match gets its own folder so same-named files can't collide.
+            let root = PathBuf::from("files").join(roots.to_string());
+            roots += 1;
+            if found.is_file() {
+                let name = found.file_name().unwrap().to_owned();
+                files.push(BackupFile {
+                    stored: root.join(name),
+                    original: found,
+                });
+                continue;
+            }
+            for file in files_under(&found) {
+                let stored = root.join(file.strip_prefix(&found)?);
+                files.push(BackupFile {
+                    original: file,
+                    stored,
+                });
+            }
+        }
+    }
+    if files.is_empty() {
+        return Ok(None);
+    }
+
+    let created = now();
+    // A new folder even in the same second, such as for the snapshot `restore` takes.
+    let (dir, ()) = dated::create(&backups_dir(cache_dir, title), created, "", |path| {
+        fs::create_dir(path)
+    })?;
+    for file in &files {
+        let stored = dir.join(&file.stored);
+        fs::create_dir_all(stored.parent().unwrap())?;
+        fs::copy(&file.original, &stored)?;
+    }
+    let count = files.len();
+    let index = BackupIndex {
+        title: title.to_owned(),
+        created,
+        files,
+    };
+    fs::write(dir.join(INDEX_JSON), serde_json::to_string_pretty(&index)?)?;
+    Ok(Some((dir, count)))
+}
+
+fn prune(cache_dir: &PathBuf, title: &str) {
+    dated::prune(versions(cache_dir, title), MAX_BACKUPS, |version| {
+        fs::remove_dir_all(version)
+    });
+}
+
+/// Copies the game's saves into a new backup folder, returning it and the number
+/// of files copied. Nothing is written when no saves are found.
+pub fn backup(
+    cache_dir: &PathBuf,
+    title: &str,
+    entry: &SaveEntry,
+) -> Result<Option<(PathBuf, usize)>, Error> {
+    let backup = snapshot(cache_dir, title, entry)?;
+    prune(cache_dir, title);
+    Ok(backup)
+}
+
+/// Copies a backup's files back to where they were taken from, returning how many.
+/// The saves being overwritten are backed up first in case the wrong version was picked.
+pub fn restore(
+    cache_dir: &PathBuf,
+    title: &str,
+    entry: Option<&SaveEntry>,
+    version: &PathBuf,
+) -> Result<usize, Error> {
+    let index: BackupIndex = serde_json::from_str(&fs::read_to_string(version.join(INDEX_JSON))?)?;
+    if let Some(entry) = entry {
+        snapshot(cache_dir, title, entry)?;
+    }
+    for file in &index.files {
+        if let Some(parent) = file.original.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(version.join(&file.stored), &file.original)?;
+    }
+    // Only now, so the version being restored can't be pruned first.
+    prune(cache_dir, title);
+    Ok(index.files.len())
+}
+
+/// Backs a game's saves up once the monitor sees a session end.
+pub struct AutoBackup {
+    cache_dir: PathBuf,
+    manifest: SaveManifest,
+    titles: HashMap<GameKey, String>,
+}
+
+impl AutoBackup {
+    pub fn new(cache_dir: &PathBuf, games: &Vec<Game>) -> Result<AutoBackup, Error> {
+        Ok(AutoBackup {
+            cache_dir: cache_dir.clone(),
+            manifest: SaveManifest::load(cache_dir)?,
+            titles: games.iter().map(|g| (g.key(), g.title.clone())).collect(),
+        })
+    }
+
+    pub fn after_session(&self, key: &GameKey) {
+        let title = match self.titles.get(key) {
+            Some(title) => title.clone(),
+            None => return,
+        };
+        let entry = match self.manifest.find(key, &title) {
+            Some(entry) => entry.clone(),
+            None => return,
+        };
+        let cache_dir = self.cache_dir.clone();
+        // Copying can take a while; don't hold up the monitor.
+        thread::spawn(move || match backup(&cache_dir, &title, &entry) {
+            Ok(Some((_dir, count))) => eprintln!("Backed up {} save files for {}", count, title),
+            Ok(None) => eprintln!("No save files found for {}", title),
+            Err(err) => eprintln!("Unable to back up saves for {}: {}", title, err),
+        });
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{GameKey, Launcher};
+use anyhow::{Error, Result};
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use serde_json;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SESSIONS_JSONL: &str = "sessions.jsonl";
+const RUNNING_JSON: &str = "running.json";
+
+/// Seconds since the unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn format_playtime(secs: u64) -> String {
+    format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+}
+
+pub fn format_timestamp(secs: u64) -> String {
+    match Local.timestamp_opt(secs as i64, 0).single() {
+        Some(time) => time.format("%Y-%m-%d %H:%M").to_string(),
+        None => "?".to_owned(),
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum SessionExit {
+    Success,
+    Error(i32),
+    /// Ended from doorways rather than by the player.
+    Stopped,
+    Unknown,
+}
+
+/// One run of a game, from launch until the monitor saw it end.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Session {
+    pub game_id: String,
+    pub launcher: Launcher,
+    pub start: u64,
+    pub end: u64,
+    pub exit: SessionExit,
+    /// Captured stdout/stderr of the game, when it was launched directly.
+    #[serde(default)]
+    pub log: Option<PathBuf>,
+}
+
+impl Session {
+    pub fn key(&self) -> GameKey {
+        GameKey {
+            launcher: self.launcher,
+            id: self.game_id.clone(),
+        }
+    }
+
+    pub fn duration(&self) -> u64 {
+        self.end.saturating_sub(self.start)
+    }
+}
+
+/// Total playtime and last played time for a single game.
+#[derive(Default, Clone, Copy)]
+pub struct PlayTotals {
+    pub playtime: u64,
+    pub last_played: Option<u64>,
+}
+
+/// Append only log of every play session, stored one json record per line.
+pub struct SessionHistory {
+    path: PathBuf,
+    sessions: Vec<Session>,
+}
+
+impl SessionHistory {
+    pub fn empty(cache_dir: &PathBuf) -> SessionHistory {
+        SessionHistory {
+            path: cache_dir.join(SESSIONS_JSONL),
+            sessions: Vec::new(),
+        }
+    }
+
+    pub fn load(cache_dir: &PathBuf) -> Result<SessionHistory, Error> {
+        let mut history = SessionHistory::empty(cache_dir);
+        if !history.path.exists() {
+            return Ok(history);
+        }
+        for (number, line) in fs::read_to_string(&history.path)?.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<Session>(line) {
+                Ok(session) => history.sessions.push(session),
+                // A partially written line shouldn't lose the rest of the history.
+                Err(err) => eprintln!("Skipping session on line {}: {}", number + 1, err),
+            }
+        }
+        Ok(history)
+    }
+
+    pub fn record(&mut self, session: Session) -> Result<(), Error> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&session)?)?;
+        self.sessions.push(session);
+        Ok(())
+    }
+
+    pub fn sessions(&self) -> &Vec<Session> {
+        &self.sessions
+    }
+
+    pub fn totals(&self) -> HashMap<GameKey, PlayTotals> {
+        let mut totals: HashMap<GameKey, PlayTotals> = HashMap::new();
+        for session in self.sessions.iter() {
+            let entry = totals.entry(session.key()).or_default();
+            entry.playtime += session.duration();
+            entry.last_played = Some(entry.last_played.unwrap_or(0).max(session.end));
+        }
+        totals
+    }
+}
+
+/// A game process that is currently being played, persisted so other
+/// invocations (e.g. `doorways stop`) can find it.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct RunningGame {
+    pub game_id: String,
+    pub launcher: Launcher,
+    pub pid: u32,
+    pub start: u64,
+    /// Process id of the doorways instance watching the game, if any.
+    pub monitor_pid: Option<u32>,
+}
+
+impl RunningGame {
+    pub fn key(&self) -> GameKey {
+        GameKey {
+            launcher: self.launcher,
+            id: self.game_id.clone(),
+        }
+    }
+}
+
+pub fn load_running(cache_dir: &PathBuf) -> Result<Vec<RunningGame>, Error> {
+    let path = cache_dir.join(RUNNING_JSON);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+pub fn save_running(cache_dir: &PathBuf, running: &Vec<RunningGame>) -> Result<(), Error> {
+    fs::write(
+        cache_dir.join(RUNNING_JSON),
+        serde_json::to_string_pretty(running)?,
+    )?;
+    Ok(())
+}
+
+/// Reads running.json, changes it and writes it back, so entries other doorways
+/// processes recorded since it was last read aren't lost.
+pub fn update_running<F>(cache_dir: &PathBuf, change: F) -> Result<(), Error>
+where
+    F: FnOnce(&mut Vec<RunningGame>),
+{
+    let mut running = load_running(cache_dir)?;
+    change(&mut running);
+    save_running(cache_dir, &running)
+}
@@ -0,0 +1,78 @@
+use crate::epic_manifests;
+use crate::{from_epic, from_steam, from_twitch, Game, Launcher};
+use anyhow::{anyhow, Error, Result};
+use epic::EpicGame;
+use serde::{Deserialize, Serialize};
+use serde_json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use steam::{app_info::AppInfo, package_info::PackageInfo, steam_game::SteamGame};
+use twitch::{TwitchDb, TwitchGame};
+
+const REFRESHED_JSON: &str = "refreshed.json";
+
+/// A store library doorways can read games from.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Source {
+    Steam,
+    Twitch,
+    Epic,
+}
+
+pub const SOURCES: [Source; 3] = [Source::Steam, Source::Twitch, Source::Epic];
+
+impl Source {
+    pub fn from_str(value: &str) -> Result<Source, Error> {
+        match value {
+            "steam" => Ok(Source::Steam),
+            "twitch" => Ok(Source::Twitch),
+            "epic" => Ok(Source::Epic),
+            _ => Err(anyhow!("Unknown source {}", value)),
+        }
+    }
+
+    pub fn launcher(self) -> Launcher {
+        match self {
+            Source::Steam => Launcher::Steam,
+            Source::Twitch => Launcher::Twitch,
+            Source::Epic => Launcher::Epic,
+        }
+    }
+
+    /// Reads every game the store currently knows about.
+    pub fn load(self, home: &PathBuf) -> Result<Vec<Game>, Error> {
+        match self {
+            Source::Steam => {
+                let app_infos = AppInfo::load()?;
+                let pkg_infos = PackageInfo::load()?;
+                Ok(from_steam(SteamGame::from(&app_infos, &pkg_infos)?))
+            }
+            Source::Twitch => {
+                let twitch_db = TwitchDb::load(&home.join(".twitch"))?;
+                Ok(from_twitch(TwitchGame::from_db(&twitch_db)?))
+            }
+            Source::Epic => Ok(from_epic(
+                EpicGame::load(&home.join(".epic"))?,
+                &epic_manifests::load(),
+            )),
+        }
+    }
+}
+
+/// When each source was last refreshed successfully.
+pub fn load_refreshed(cache_dir: &PathBuf) -> Result<HashMap<Source, u64>, Error> {
+    let path = cache_dir.join(REFRESHED_JSON);
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+pub fn save_refreshed(cache_dir: &PathBuf, refreshed: &HashMap<Source, u64>) -> Result<(), Error> {
+    fs::write(
+        cache_dir.join(REFRESHED_JSON),
+        serde_json::to_string_pretty(refreshed)?,
+    )?;
+    Ok(())
+}
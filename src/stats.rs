@@ -0,0 +1,378 @@
+use crate::sessions::{format_playtime, format_timestamp, Session};
+use crate::{Game, Launcher};
+use anyhow::{anyhow, Error, Result};
+use chrono::{Datelike, Duration, Local, NaiveDate, TimeZone};
+use serde::Serialize;
+use serde_json;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+const MOST_LAUNCHED: usize = 10;
+
+#[derive(Copy, Clone)]
+pub enum StatsFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+impl StatsFormat {
+    pub fn from_str(value: &str) -> StatsFormat {
+        match value {
+            "csv" => StatsFormat::Csv,
+            "json" => StatsFormat::Json,
+            _ => StatsFormat::Text,
+        }
+    }
+}
+
+/// Parses a `YYYY-MM-DD` date into the unix time of local midnight.
+/// `end_of_day` moves the result to the following midnight so ranges are inclusive.
+pub fn parse_date(value: &str, end_of_day: bool) -> Result<u64, Error> {
+    let mut date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|err| anyhow!("Invalid date {}: {}", value, err))?;
+    if end_of_day {
+        date = date + Duration::days(1);
+    }
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+    let time = Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .ok_or_else(|| anyhow!("Invalid local time for {}", value))?;
+    Ok(time.timestamp().max(0) as u64)
+}
+
+fn local_date(secs: u64) -> Option<NaiveDate> {
+    Local
+        .timestamp_opt(secs as i64, 0)
+        .single()
+        .map(|time| time.date_naive())
+}
+
+#[derive(Serialize, Clone)]
+pub struct GameStats {
+    title: String,
+    launcher: Launcher,
+    playtime: u64,
+    launches: usize,
+    last_played: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct LauncherStats {
+    launcher: Launcher,
+    playtime: u64,
+    launches: usize,
+    games: usize,
+}
+
+#[derive(Serialize)]
+pub struct WeekStats {
+    week: String,
+    playtime: u64,
+    launches: usize,
+}
+
+#[derive(Serialize, Default)]
+pub struct Streaks {
+    /// Most consecutive days with at least one session.
+    longest: usize,
+    longest_start: Option<String>,
+    /// Consecutive days played up to today (or yesterday).
+    current: usize,
+}
+
+#[derive(Serialize)]
+pub struct Report {
+    from: Option<u64>,
+    to: Option<u64>,
+    total_playtime: u64,
+    games: Vec<GameStats>,
+    launchers: Vec<LauncherStats>,
+    weeks: Vec<WeekStats>,
+    streaks: Streaks,
+    most_launched: Vec<GameStats>,
+    never_played: Vec<String>,
+}
+
+impl Report {
+    pub fn build(
+        games: &Vec<Game>,
+        sessions: &Vec<Session>,
+        from: Option<u64>,
+        to: Option<u64>,
+    ) -> Report {
+        let sessions: Vec<&Session> = sessions
+            .iter()
+            .filter(|s| from.map_or(true, |from| s.start >= from))
+            .filter(|s| to.map_or(true, |to| s.start < to))
+            .collect();
+        let titles: HashMap<(Launcher, &str), &str> = games
+            .iter()
+            .map(|g| ((g.launcher, g.id.as_str()), g.title.as_str()))
+            .collect();
+
+        let mut per_game: HashMap<(Launcher, &str), GameStats> = HashMap::new();
+        let mut per_launcher: HashMap<Launcher, LauncherStats> = HashMap::new();
+        let mut per_week: BTreeMap<String, WeekStats> = BTreeMap::new();
+        let mut days: BTreeSet<NaiveDate> = BTreeSet::new();
+        for session in sessions.iter() {
+            let key = (session.launcher, session.game_id.as_str());
+            let game = per_game.entry(key).or_insert_with(|| GameStats {
+                title: titles
+                    .get(&key)
+                    .cloned()
+                    .unwrap_or(session.game_id.as_str())
+                    .to_owned(),
+                launcher: session.launcher,
+                playtime: 0,
+                launches: 0,
+                last_played: None,
+            });
+            game.playtime += session.duration();
+            game.launches += 1;
+            game.last_played = Some(game.last_played.unwrap_or(0).max(session.end));
+
+            let launcher = per_launcher
+                .entry(session.launcher)
+                .or_insert_with(|| LauncherStats {
+                    launcher: session.launcher,
+                    playtime: 0,
+                    launches: 0,
+                    games: 0,
+                });
+            launcher.playtime += session.duration();
+            launcher.launches += 1;
+
+            if let Some(date) = local_date(session.start) {
+                let iso = date.iso_week();
+                let week = format!("{}-W{:02}", iso.year(), iso.week());
+                let entry = per_week.entry(week.clone()).or_insert_with(|| WeekStats {
+                    week,
+                    playtime: 0,
+                    launches: 0,
+                });
+                entry.playtime += session.duration();
+                entry.launches += 1;
+                days.insert(date);
+            }
+        }
+        for (launcher, _id) in per_game.keys() {
+            if let Some(stats) = per_launcher.get_mut(launcher) {
+                stats.games += 1;
+            }
+        }
+
+        let mut game_stats: Vec<GameStats> = per_game.into_iter().map(|(_k, v)| v).collect();
+        game_stats.sort_by(|a, b| b.playtime.cmp(&a.playtime).then(a.title.cmp(&b.title)));
+        let mut most_launched = game_stats.clone();
+        most_launched.sort_by(|a, b| b.launches.cmp(&a.launches).then(a.title.cmp(&b.title)));
+        most_launched.truncate(MOST_LAUNCHED);
+
+        let mut launchers: Vec<LauncherStats> = per_launcher.into_iter().map(|(_k, v)| v).collect();
+        launchers.sort_by(|a, b| b.playtime.cmp(&a.playtime));
+
+        let played: HashSet<(Launcher, &str)> = sessions
+            .iter()
+            .map(|s| (s.launcher, s.game_id.as_str()))
+            .collect();
+        let mut never_played: Vec<String> = games
+            .iter()
+            .filter(|g| g.installed && !played.contains(&(g.launcher, g.id.as_str())))
+            .map(|g| g.title.clone())
+            .collect();
+        never_played.sort();
+
+        Report {
+            from,
+            to,
+            total_playtime: game_stats.iter().map(|g| g.playtime).sum(),
+            games: game_stats,
+            launchers,
+            weeks: per_week.into_iter().map(|(_k, v)| v).collect(),
+            streaks: streaks(&days),
+            most_launched,
+            never_played,
+        }
+    }
+
+    pub fn render(&self, format: StatsFormat) -> Result<String, Error> {
+        Ok(match format {
+            StatsFormat::Text => self.to_text(),
+            StatsFormat::Csv => self.to_csv(),
+            StatsFormat::Json => serde_json::to_string_pretty(self)?,
+        })
+    }
+
+    fn to_text(&self) -> String {
+        let mut out = String::new();
+        let range = |time: Option<u64>| time.map_or("-".to_owned(), format_timestamp);
+        out.push_str(&format!(
+            "Play statistics {} to {}\nTotal playtime: {}\n",
+            range(self.from),
+            range(self.to),
+            format_playtime(self.total_playtime)
+        ));
+
+        out.push_str("\nPer game:\n");
+        for game in self.games.iter() {
+            out.push_str(&format!(
+                "  {:<50} {:>10} {:>4} launches  {:?}\n",
+                game.title,
+                format_playtime(game.playtime),
+                game.launches,
+                game.launcher
+            ));
+        }
+
+        out.push_str("\nPer launcher:\n");
+        for launcher in self.launchers.iter() {
+            out.push_str(&format!(
+                "  {:<10} {:>10} {:>4} launches {:>4} games\n",
+                format!("{:?}", launcher.launcher),
+                format_playtime(launcher.playtime),
+                launcher.launches,
+                launcher.games
+            ));
+        }
+
+        out.push_str("\nPer week:\n");
+        for week in self.weeks.iter() {
+            out.push_str(&format!(
+                "  {:<10} {:>10} {:>4} launches\n",
+                week.week,
+                format_playtime(week.playtime),
+                week.launches
+            ));
+        }
+
+        out.push_str(&format!(
+            "\nStreaks:\n  Longest: {} days{}\n  Current: {} days\n",
+            self.streaks.longest,
+            self.streaks
+                .longest_start
+                .as_ref()
+                .map_or("".to_owned(), |start| format!(" (from {})", start)),
+            self.streaks.current
+        ));
+
+        out.push_str("\nMost launched:\n");
+        for game in self.most_launched.iter() {
+            out.push_str(&format!("  {:<50} {:>4}\n", game.title, game.launches));
+        }
+
+        out.push_str("\nInstalled but never played:\n");
+        for title in self.never_played.iter() {
+            out.push_str(&format!("  {}\n", title));
+        }
+        out
+    }
+
+    /// Every section is written as its own table; the first column names the section.
+    fn to_csv(&self) -> String {
+        let mut out = String::new();
+        out.push_str("section,name,launcher,playtime_secs,launches,last_played\n");
+        for game in self.games.iter() {
+            out.push_str(&csv_game("game", game));
+        }
+        for launcher in self.launchers.iter() {
+            let name = format!("{:?}", launcher.launcher);
+            out.push_str(&csv_row(&[
+                "launcher",
+                &name,
+                &name,
+                &launcher.playtime.to_string(),
+                &launcher.launches.to_string(),
+                "",
+            ]));
+        }
+        for week in self.weeks.iter() {
+            out.push_str(&csv_row(&[
+                "week",
+                &week.week,
+                "",
+                &week.playtime.to_string(),
+                &week.launches.to_string(),
+                "",
+            ]));
+        }
+        for game in self.most_launched.iter() {
+            out.push_str(&csv_game("most_launched", game));
+        }
+        for title in self.never_played.iter() {
+            out.push_str(&csv_row(&["never_played", title, "", "0", "0", ""]));
+        }
+        out.push_str(&csv_row(&[
+            "streak_longest",
+            self.streaks
+                .longest_start
+                .as_ref()
+                .map_or("", |s| s.as_str()),
+            "",
+            "",
+            &self.streaks.longest.to_string(),
+            "",
+        ]));
+        out.push_str(&csv_row(&[
+            "streak_current",
+            "",
+            "",
+            "",
+            &self.streaks.current.to_string(),
+            "",
+        ]));
+        out
+    }
+}
+
+fn csv_game(section: &str, game: &GameStats) -> String {
+    csv_row(&[
+        section,
+        &game.title,
+        &format!("{:?}", game.launcher),
+        &game.playtime.to_string(),
+        &game.launches.to_string(),
+        &game.last_played.map_or("".to_owned(), format_timestamp),
+    ])
+}
+
+fn csv_row(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            if field.contains(|c| c == ',' || c == '"' || c == '\n') {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    format!("{}\n", fields.join(","))
+}
+
+fn streaks(days: &BTreeSet<NaiveDate>) -> Streaks {
+    let mut streaks = Streaks::default();
+    let mut run = 0;
+    let mut run_start: Option<NaiveDate> = None;
+    let mut previous: Option<NaiveDate> = None;
+    for day in days.iter() {
+        match previous {
+            Some(prev) if *day == prev + Duration::days(1) => run += 1,
+            _ => {
+                run = 1;
+                run_start = Some(*day);
+            }
+        }
+        if run > streaks.longest {
+            streaks.longest = run;
+            streaks.longest_start = run_start.map(|d| d.format("%Y-%m-%d").to_string());
+        }
+        previous = Some(*day);
+    }
+    let today = Local::now().date_naive();
+    if let Some(last) = previous {
+        if last == today || last + Duration::days(1) == today {
+            streaks.current = run;
+        }
+    }
+    streaks
+}
@@ -0,0 +1,87 @@
+use crate::Launcher;
+use std::process::Command;
+
+/// Library management a store client can do for us.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StoreAction {
+    Install,
+    Uninstall,
+    Verify,
+}
+
+impl StoreAction {
+    pub fn name(self) -> &'static str {
+        match self {
+            StoreAction::Install => "install",
+            StoreAction::Uninstall => "uninstall",
+            StoreAction::Verify => "verify",
+        }
+    }
+
+    /// What a tile shows while the action is under way.
+    pub fn progress(self) -> &'static str {
+        match self {
+            StoreAction::Install => "Installing...",
+            StoreAction::Uninstall => "Uninstalling...",
+            StoreAction::Verify => "Verifying...",
+        }
+    }
+
+    /// What a tile shows once a store command that does the work itself is done.
+    pub fn done(self) -> &'static str {
+        match self {
+            StoreAction::Install => "Installed",
+            StoreAction::Uninstall => "Uninstalled",
+            StoreAction::Verify => "Files verified",
+        }
+    }
+}
+
+pub struct StoreCommand {
+    pub command: Command,
+    /// Whether the command does the work itself. Otherwise it only hands a URL to
+    /// the store client, which carries on without us.
+    pub waits: bool,
+}
+
+/// Hands `url` to whichever application is registered for its scheme.
+pub fn open_url(url: &str) -> Command {
+    let mut open = Command::new("cmd");
+    open.args(&["/C", "start", url]);
+    open
+}
+
+fn url(url: String) -> Option<StoreCommand> {
+    Some(StoreCommand {
+        command: open_url(&url),
+        waits: false,
+    })
+}
+
+fn legendary(args: &[&str]) -> Option<StoreCommand> {
+    let mut command = Command::new("legendary");
+    command.args(args);
+    Some(StoreCommand {
+        command,
+        waits: true,
+    })
+}
+
+/// The command that has the game's store perform `action`, if it supports it.
+/// Epic games take their catalog id to install, and their app name otherwise.
+pub fn command(launcher: Launcher, id: &str, action: StoreAction) -> Option<StoreCommand> {
+    match (launcher, action) {
+        (Launcher::Steam, StoreAction::Install) => url(format!("steam://install/{}", id)),
+        (Launcher::Steam, StoreAction::Uninstall) => url(format!("steam://uninstall/{}", id)),
+        (Launcher::Steam, StoreAction::Verify) => url(format!("steam://validate/{}", id)),
+        (Launcher::Epic, StoreAction::Install) => url(format!(
+            "com.epicgames.launcher://apps/{}?action=install",
+            id
+        )),
+        // The Epic launcher has no URLs for these, so go through Legendary. Asking
+        // first is left to the launcher and the CLI.
+        (Launcher::Epic, StoreAction::Uninstall) => legendary(&["uninstall", "-y", id]),
+        (Launcher::Epic, StoreAction::Verify) => legendary(&["verify", id]),
+        _ => None,
+    }
+}
@@ -0,0 +1,110 @@
+use crate::crop::Crop;
+use crate::image_cache::{hash, write_whole};
+use crate::images::thumbnail;
+use crate::{MAX_TILE_HEIGHT, MAX_TILE_WIDTH};
+use anyhow::{Error, Result};
+use image::RgbaImage;
+use std::collections::HashSet;
+use std::convert::TryInto;
+use std::fs;
+use std::path::PathBuf;
+
+/// Resized tiles kept as raw RGBA after their width and height, so startup only
+/// has to read them back. Keyed by a hash of the source image, the tile size and
+/// any crop, so a changed image or tile shape gets a new thumbnail.
+pub struct Thumbnails {
+    folder: PathBuf,
+}
+
+impl Thumbnails {
+    pub fn new(folder: &PathBuf) -> Thumbnails {
+        if let Err(err) = fs::create_dir_all(folder) {
+            eprintln!("Unable to create {}: {}", folder.display(), err);
+        }
+        Thumbnails {
+            folder: folder.clone(),
+        }
+    }
+
+    fn path(&self, source: &[u8], crop: Option<Crop>) -> PathBuf {
+        let crop = crop.map(|c| format!("-{}", c.key())).unwrap_or_default();
+        self.folder.join(format!(
+            "{}-{}x{}{}.rgba",
+            hash(source),
+            MAX_TILE_WIDTH,
+            MAX_TILE_HEIGHT,
+            crop
+        ))
+    }
+
+    fn read(&self, path: &PathBuf) -> Option<RgbaImage> {
+        let contents = fs::read(path).ok()?;
+        if contents.len() < 8 {
+            return None;
+        }
+        let width = u32::from_le_bytes(contents[0..4].try_into().ok()?);
+        let height = u32::from_le_bytes(contents[4..8].try_into().ok()?);
+        RgbaImage::from_raw(width, height, contents[8..].to_vec())
+    }
+
+    fn write(&self, path: &PathBuf, img: &RgbaImage) -> Result<(), Error> {
+        let mut contents = Vec::with_capacity(8 + img.len());
+        contents.extend_from_slice(&img.width().to_le_bytes());
+        contents.extend_from_slice(&img.height().to_le_bytes());
+        contents.extend_from_slice(img);
+        write_whole(path, &contents)
+    }
+
+    /// Deletes thumbnails not made from one of the `sources`, given as hashes.
+    /// Returns the number removed and their size.
+    pub fn prune(&self, sources: &HashSet<String>) -> Result<(usize, u64), Error> {
+        let mut removed = (0, 0);
+        for entry in fs::read_dir(&self.folder)?.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let source = name.split('-').next().unwrap_or("");
+            if sources.contains(source) || !entry.path().is_file() {
+                continue;
+            }
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            fs::remove_file(entry.path())?;
+            removed.0 += 1;
+            removed.1 += size;
+        }
+        Ok(removed)
+    }
+
+    /// Deletes the thumbnails made from `source`, so they aren't kept once the image is replaced.
+    pub fn remove(&self, source: &[u8]) {
+        let prefix = format!("{}-", hash(source));
+        let entries = match fs::read_dir(&self.folder) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            if !entry.file_name().to_string_lossy().starts_with(&prefix) {
+                continue;
+            }
+            if let Err(err) = fs::remove_file(entry.path()) {
+                eprintln!(
+                    "Unable to remove thumbnail {}: {}",
+                    entry.path().display(),
+                    err
+                );
+            }
+        }
+    }
+
+    /// The tile-sized version of `source`, cropped if asked, resizing it only if it
+    /// isn't cached yet.
+    pub fn get(&self, source: &[u8], crop: Option<Crop>) -> Result<RgbaImage, Error> {
+        let path = self.path(source, crop);
+        if let Some(img) = self.read(&path) {
+            return Ok(img);
+        }
+        let img = thumbnail(source, crop)?;
+        if let Err(err) = self.write(&path, &img) {
+            eprintln!("Unable to cache thumbnail {}: {}", path.display(), err);
+        }
+        Ok(img)
+    }
+}
@@ -0,0 +1,156 @@
+use crate::sources::{Source, SOURCES};
+use crate::Game;
+use anyhow::{Error, Result};
+use notify::{recommended_watcher, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Stores write several files per change, so wait for them to go quiet before reloading.
+const SETTLE: Duration = Duration::from_secs(2);
+
+pub fn steam_dir() -> Option<PathBuf> {
+    use winreg::enums::*;
+    use winreg::RegKey;
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let steam = hkcu.open_subkey(r"Software\Valve\Steam").ok()?;
+    let path: String = steam.get_value("SteamPath").ok()?;
+    Some(PathBuf::from(path))
+}
+
+fn normalize(path: &PathBuf) -> String {
+    let path = path.to_string_lossy().replace('/', "\\").to_lowercase();
+    path.trim_end_matches('\\').to_owned()
+}
+
+/// Every Steam library folder, from `libraryfolders.vdf`. Older Steam versions
+/// list `"1" "D:\\Games"`, newer ones nest a `"path"` under each number.
+pub fn steam_libraries(steam: &PathBuf) -> Vec<PathBuf> {
+    let mut libraries = vec![steam.clone()];
+    let vdf = match fs::read_to_string(steam.join("steamapps").join("libraryfolders.vdf")) {
+        Ok(vdf) => vdf,
+        Err(_) => return libraries,
+    };
+    for line in vdf.lines() {
+        let fields: Vec<&str> = line.split('"').collect();
+        // `"key"<tabs>"value"` splits into five, with the quoted parts at 1 and 3.
+        if fields.len() != 5 {
+            continue;
+        }
+        let (key, value) = (fields[1], fields[3]);
+        if key == "path" || (key.parse::<u32>().is_ok() && value.contains('\\')) {
+            let library = PathBuf::from(value.replace("\\\\", "\\"));
+            // The registry's SteamPath is lower case with forward slashes.
+            let same = |path: &PathBuf| normalize(path) == normalize(&library);
+            if !libraries.iter().any(same) {
+                libraries.push(library);
+            }
+        }
+    }
+    libraries
+}
+
+/// Files each source is loaded from.
+fn watched(source: Source, home: &PathBuf) -> Vec<(PathBuf, RecursiveMode)> {
+    match source {
+        // appmanifests live directly in each library's steamapps; appcache holds app
+        // and package info next to large caches that aren't worth watching.
+        Source::Steam => match steam_dir() {
+            Some(steam) => {
+                let mut paths: Vec<(PathBuf, RecursiveMode)> = steam_libraries(&steam)
+                    .into_iter()
+                    .map(|library| (library.join("steamapps"), RecursiveMode::NonRecursive))
+                    .collect();
+                paths.push((steam.join("appcache"), RecursiveMode::NonRecursive));
+                paths
+            }
+            None => Vec::new(),
+        },
+        Source::Twitch => vec![(home.join(".twitch"), RecursiveMode::Recursive)],
+        Source::Epic => vec![(home.join(".epic"), RecursiveMode::Recursive)],
+    }
+}
+
+/// Reloads sources whose files change while the launcher is open, sending them
+/// to `updates`.
+pub struct LibraryWatcher {
+    // Watching stops when this is dropped.
+    _watcher: RecommendedWatcher,
+}
+
+impl LibraryWatcher {
+    pub fn start(
+        home: &PathBuf,
+        updates: mpsc::Sender<(Source, Vec<Game>)>,
+    ) -> Result<LibraryWatcher, Error> {
+        let paths: Vec<(Source, PathBuf, RecursiveMode)> = SOURCES
+            .iter()
+            .flat_map(|source| {
+                watched(*source, home)
+                    .into_iter()
+                    .map(move |(path, mode)| (*source, path, mode))
+            })
+            .filter(|(source, path, _mode)| {
+                if !path.exists() {
+                    eprintln!("Not watching {:?}: {} is missing", source, path.display());
+                }
+                path.exists()
+            })
+            .collect();
+
+        let (changed, changes) = mpsc::channel::<Source>();
+        let sources: Vec<(Source, PathBuf)> = paths
+            .iter()
+            .map(|(source, path, _mode)| (*source, path.clone()))
+            .collect();
+        let mut watcher = recommended_watcher(move |event: notify::Result<Event>| {
+            let event = match event {
+                Ok(event) => event,
+                Err(err) => {
+                    eprintln!("Error watching library: {}", err);
+                    return;
+                }
+            };
+            for (source, path) in &sources {
+                if event.paths.iter().any(|p| p.starts_with(path)) {
+                    let _ = changed.send(*source);
+                }
+            }
+        })?;
+        for (_source, path, mode) in &paths {
+            watcher.watch(path, *mode)?;
+        }
+
+        let home = home.clone();
+        thread::spawn(move || reload(changes, updates, home));
+        Ok(LibraryWatcher { _watcher: watcher })
+    }
+}
+
+/// Reloads each changed source once its files have settled. Ends when the watcher is dropped.
+fn reload(
+    changes: mpsc::Receiver<Source>,
+    updates: mpsc::Sender<(Source, Vec<Game>)>,
+    home: PathBuf,
+) {
+    while let Ok(source) = changes.recv() {
+        let mut pending = HashSet::new();
+        pending.insert(source);
+        while let Ok(source) = changes.recv_timeout(SETTLE) {
+            pending.insert(source);
+        }
+        for source in pending {
+            match source.load(&home) {
+                Ok(games) => {
+                    if updates.send((source, games)).is_err() {
+                        return;
+                    }
+                }
+                Err(err) => eprintln!("Unable to refresh {:?}: {}", source, err),
+            }
+        }
+    }
+}